@@ -0,0 +1,32 @@
+use ekiden_rust_sdk::{KeyPair, KeyRole, Keystore};
+
+/// Import hex private keys into an encrypted keystore:
+///
+/// EKIDEN_KEYSTORE_PASSWORD=... cargo run --example keystore -- <dir> <root> [funding] [trading]
+///
+/// Keys that are not given are generated.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    let keystore_dir = args
+        .get(1)
+        .ok_or_else(|| anyhow::anyhow!("Missing argument for keystore directory"))?;
+    let password = std::env::var("EKIDEN_KEYSTORE_PASSWORD")?;
+    let keystore = Keystore::open(keystore_dir)?;
+
+    for (i, role) in KeyRole::ALL.into_iter().enumerate() {
+        let key_pair = match args.get(i + 2) {
+            Some(private_key) => KeyPair::from_private_key(private_key)?,
+            None => KeyPair::generate(),
+        };
+        keystore.save(role, &key_pair, &password)?;
+        println!(
+            "Saved {} key {} to {}",
+            role,
+            key_pair.public_key(),
+            keystore.path(role).display()
+        );
+    }
+
+    Ok(())
+}
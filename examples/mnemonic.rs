@@ -0,0 +1,40 @@
+use ekiden_rust_sdk::{AccountMnemonic, KeyRole, Keystore};
+
+/// Restore (or create) all three account keys from one backup phrase:
+///
+/// EKIDEN_KEYSTORE_PASSWORD=... [EKIDEN_MNEMONIC="word ..."] cargo run --example mnemonic -- <dir> [account]
+///
+/// Without `EKIDEN_MNEMONIC` a new 24-word phrase is generated and printed once.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    let keystore_dir = args
+        .get(1)
+        .ok_or_else(|| anyhow::anyhow!("Missing argument for keystore directory"))?;
+    let account: u32 = args.get(2).map(|a| a.parse()).transpose()?.unwrap_or(0);
+    let password = std::env::var("EKIDEN_KEYSTORE_PASSWORD")?;
+    let keystore = Keystore::open(keystore_dir)?;
+
+    let mnemonic = match std::env::var("EKIDEN_MNEMONIC") {
+        Ok(phrase) => AccountMnemonic::from_phrase(&phrase)?,
+        Err(_) => {
+            let mnemonic = AccountMnemonic::generate(24)?;
+            println!("Write down your backup phrase and keep it offline:");
+            println!("{}", mnemonic.phrase().expose_secret());
+            mnemonic
+        }
+    };
+
+    let keys = mnemonic.derive_account(account)?;
+    keys.save(&keystore, &password)?;
+    for role in KeyRole::ALL {
+        println!(
+            "Saved {} key {} to {}",
+            role,
+            keys.get(role).public_key(),
+            keystore.path(role).display()
+        );
+    }
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
-use aptos_crypto::ValidCryptoMaterialStringExt;
+use ekiden_rust_sdk::signer::signature_hex;
 use ekiden_rust_sdk::{
-    ActionPayload, EkidenClient, EkidenClientBuilder, KeyPair, OrderCancelAllAction, OrderCreate,
-    OrderCreateAction, SendIntentParams, TimeInForce,
+    generate_client_order_id, ActionPayload, EkidenClient, EkidenClientBuilder,
+    OrderCancelAllAction, OrderCreate, OrderCreateAction, SendIntentParams, TimeInForce,
 };
 use std::time::Duration;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -13,36 +13,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("🚀 Starting Ekiden SDK Basic Example");
 
-    // Check for private key argument, otherwise generate a new key pair
+    // Keys are read from an encrypted keystore, see the keystore example
     let args: Vec<String> = std::env::args().collect();
-    let owner = args
+    let keystore_dir = args
         .get(1)
-        .ok_or_else(|| anyhow::anyhow!("Missing argument for private key"))?;
-    let owner_key = KeyPair::from_private_key(owner)?;
-    let funding = args
-        .get(2)
-        .ok_or_else(|| anyhow::anyhow!("Missing argument for private key"))?;
-    let funding_key = KeyPair::from_private_key(funding)?;
-    let trading = args
-        .get(3)
-        .ok_or_else(|| anyhow::anyhow!("Missing argument for private key"))?;
-    let trading_key = KeyPair::from_private_key(trading)?;
-
-    println!("Public key: {}", owner_key.public_key());
-    println!("Private key: {}", owner_key.private_key());
-
-    println!("Public funding key: {}", funding_key.public_key());
-    println!("Private funding key: {}", funding_key.private_key());
-
-    println!("Public trading key: {}", trading_key.public_key());
-    println!("Private trading key: {}", trading_key.private_key());
+        .ok_or_else(|| anyhow::anyhow!("Missing argument for keystore directory"))?;
+    let password = std::env::var("EKIDEN_KEYSTORE_PASSWORD")?;
 
     // Create client with configuration
     let client = EkidenClientBuilder::new()
         .staging()? // Use local development environment
-        .private_key(owner_key.private_key())
-        .funding_private_key(trading_key.private_key())
-        .trading_private_key(trading_key.private_key())
+        .keystore(keystore_dir, password)
         .timeout(Duration::from_secs(10))
         .with_logging(true)
         .build_and_auth()
@@ -50,7 +31,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Check if we can connect (optional - for demo purposes)
     println!("✅ Client created successfully");
-    demonstrate_authenticated_api(&client, &trading_key.private_key()).await?;
+    demonstrate_authenticated_api(&client).await?;
 
     // Try to authenticate (this might fail if no local API is running)
 
@@ -60,7 +41,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 async fn demonstrate_authenticated_api(
     client: &EkidenClient,
-    private_key_str: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("\n🔐 Demonstrating Authenticated API calls...");
     let market_addr = "0xa3a64c01b11ba0ec46c7b5715ac559d236f8c0b5667eafa3a5ec8e7b65cdc2e6";
@@ -74,18 +54,16 @@ async fn demonstrate_authenticated_api(
         .unwrap()
         .as_secs();
 
-    let signature = client
-        .sign_intent(private_key_str, &payload, nonce)
-        .map_err(|e| {
-            println!("Failed to sign intent: {}", e);
-            e
-        })?;
+    let signature = client.sign_intent(&payload, nonce).await.map_err(|e| {
+        println!("Failed to sign intent: {}", e);
+        e
+    })?;
 
     // Send intent to the API
     let intent_body = SendIntentParams {
         payload,
         nonce,
-        signature: signature.to_encoded_string()?,
+        signature: signature_hex(&signature),
     };
 
     // Get user portfolio
@@ -109,6 +87,9 @@ async fn demonstrate_authenticated_api(
         leverage: 20u64,
         is_cross: true,
         time_in_force: Some(TimeInForce::GTC),
+        client_order_id: Some(generate_client_order_id()),
+        trigger_price: None,
+        reduce_only: false,
     };
 
     // Create the action with vector of orders
@@ -116,22 +97,20 @@ async fn demonstrate_authenticated_api(
         orders: vec![order], // Vector containing the order
     });
 
-    let signature = client
-        .sign_intent(private_key_str, &payload, nonce)
-        .map_err(|e| {
-            println!("Failed to sign intent: {}", e);
-            e
-        })?;
+    let signature = client.sign_intent(&payload, nonce).await.map_err(|e| {
+        println!("Failed to sign intent: {}", e);
+        e
+    })?;
 
     // Send intent to the API
     let intent_body = SendIntentParams {
         payload,
         nonce,
-        signature: signature.to_encoded_string()?,
+        signature: signature_hex(&signature),
     };
 
-    // Get user portfolio
-    let send_res = client.send_intent(intent_body).await;
+    // Retry safely: the order is looked up by client order id before resubmitting
+    let send_res = client.send_intent_safe(intent_body).await;
     println!("Send Intent Response: {:?}", send_res);
 
     Ok(())
@@ -1,4 +1,4 @@
-use ekiden_rust_sdk::{EkidenClient, EkidenClientBuilder, KeyPair};
+use ekiden_rust_sdk::{EkidenClient, EkidenClientBuilder};
 use std::time::Duration;
 
 #[tokio::main]
@@ -8,36 +8,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("🚀 Starting Ekiden SDK Basic Example");
 
-    // Check for private key argument, otherwise generate a new key pair
+    // Keys are read from an encrypted keystore, see the keystore example
     let args: Vec<String> = std::env::args().collect();
-    let owner = args
+    let keystore_dir = args
         .get(1)
-        .ok_or_else(|| anyhow::anyhow!("Missing argument for private key"))?;
-    let owner_key = KeyPair::from_private_key(owner)?;
-    let funding = args
-        .get(2)
-        .ok_or_else(|| anyhow::anyhow!("Missing argument for private key"))?;
-    let funding_key = KeyPair::from_private_key(funding)?;
-    let trading = args
-        .get(3)
-        .ok_or_else(|| anyhow::anyhow!("Missing argument for private key"))?;
-    let trading_key = KeyPair::from_private_key(trading)?;
-
-    println!("Public key: {}", owner_key.public_key());
-    println!("Private key: {}", owner_key.private_key());
-
-    println!("Public funding key: {}", funding_key.public_key());
-    println!("Private funding key: {}", funding_key.private_key());
-
-    println!("Public trading key: {}", trading_key.public_key());
-    println!("Private trading key: {}", trading_key.private_key());
+        .ok_or_else(|| anyhow::anyhow!("Missing argument for keystore directory"))?;
+    let password = std::env::var("EKIDEN_KEYSTORE_PASSWORD")?;
 
     // Create client with configuration
     let client = EkidenClientBuilder::new()
         .staging()? // Use local development environment
-        .private_key(owner_key.private_key())
-        .funding_private_key(funding_key.private_key())
-        .trading_private_key(trading_key.private_key())
+        .keystore(keystore_dir, password)
         .timeout(Duration::from_secs(10))
         .with_logging(true)
         .build_and_auth()
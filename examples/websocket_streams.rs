@@ -14,7 +14,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let key_pair = if args.len() > 1 {
         // Use provided private key
         let private_key = &args[1];
-        println!("Using provided private key {}", private_key);
+        println!("Using provided private key");
         KeyPair::from_private_key(private_key)?
     } else {
         // Generate a new key pair for this example
@@ -26,7 +26,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Create client
     let client = EkidenClient::default_config()?;
-    client.set_private_key(&key_pair.private_key()).await?;
+    client
+        .set_private_key(key_pair.private_key().expose_secret())
+        .await?;
 
     // Connect to WebSockets
     match client.connect_websocket().await {
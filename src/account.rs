@@ -0,0 +1,239 @@
+use crate::client::EkidenClient;
+use crate::error::{EkidenError, Result};
+use crate::orders::OrderBuilder;
+use crate::types::{LeverageResponse, OrderSide};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Margin mode of a market
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarginMode {
+    Cross,
+    Isolated,
+}
+
+impl MarginMode {
+    pub fn from_is_cross(is_cross: bool) -> Self {
+        if is_cross {
+            MarginMode::Cross
+        } else {
+            MarginMode::Isolated
+        }
+    }
+
+    pub fn is_cross(&self) -> bool {
+        matches!(self, MarginMode::Cross)
+    }
+}
+
+/// Leverage and margin mode of a market
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketSettings {
+    pub leverage: u64,
+    /// `None` until the gateway reports the margin mode of the market
+    pub margin_mode: Option<MarginMode>,
+}
+
+/// Per-market leverage and margin mode of the trading account.
+///
+/// Settings are read and written with the trading credential, validated
+/// against the market's maximum leverage and cached for order builders.
+#[derive(Debug, Clone)]
+pub struct AccountSettings {
+    client: EkidenClient,
+    settings: Arc<RwLock<HashMap<String, MarketSettings>>>,
+    max_leverage: Arc<RwLock<HashMap<String, u32>>>,
+}
+
+impl AccountSettings {
+    /// Create account settings for a client
+    pub fn new(client: EkidenClient) -> Self {
+        Self {
+            client,
+            settings: Arc::new(RwLock::new(HashMap::new())),
+            max_leverage: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Get the settings of a market, from the cache if available
+    pub async fn get(&self, market_addr: &str) -> Result<MarketSettings> {
+        if let Some(settings) = self.cached(market_addr).await {
+            return Ok(settings);
+        }
+        self.refresh(market_addr).await
+    }
+
+    /// Get the cached settings of a market
+    pub async fn cached(&self, market_addr: &str) -> Option<MarketSettings> {
+        self.settings.read().await.get(market_addr).copied()
+    }
+
+    /// Fetch the settings of a market from the gateway
+    pub async fn refresh(&self, market_addr: &str) -> Result<MarketSettings> {
+        let response = self.client.get_user_leverage(market_addr).await?;
+        Ok(self.store(market_addr, response).await)
+    }
+
+    /// Drop the cached settings of a market
+    pub async fn invalidate(&self, market_addr: &str) {
+        self.settings.write().await.remove(market_addr);
+    }
+
+    /// Set the leverage of a market, keeping its margin mode
+    pub async fn set_leverage(&self, market_addr: &str, leverage: u64) -> Result<MarketSettings> {
+        self.validate_leverage(market_addr, leverage).await?;
+        let response = self.client.set_user_leverage(market_addr, leverage).await?;
+        Ok(self.store(market_addr, response).await)
+    }
+
+    /// Set the margin mode of a market, keeping its leverage
+    pub async fn set_margin_mode(
+        &self,
+        market_addr: &str,
+        margin_mode: MarginMode,
+    ) -> Result<MarketSettings> {
+        let current = self.get(market_addr).await?;
+        self.set(market_addr, current.leverage, margin_mode).await
+    }
+
+    /// Set both the leverage and the margin mode of a market
+    pub async fn set(
+        &self,
+        market_addr: &str,
+        leverage: u64,
+        margin_mode: MarginMode,
+    ) -> Result<MarketSettings> {
+        self.validate_leverage(market_addr, leverage).await?;
+        let mut response = self
+            .client
+            .set_user_leverage_and_margin_mode(market_addr, leverage, Some(margin_mode.is_cross()))
+            .await?;
+
+        // Not every gateway echoes the margin mode back; when it does, it
+        // must be the requested one
+        let echoed = response.is_cross.map(MarginMode::from_is_cross);
+        response.is_cross.get_or_insert(margin_mode.is_cross());
+        let settings = self.store(market_addr, response).await;
+        match echoed {
+            Some(echoed) if echoed != margin_mode => Err(EkidenError::general(format!(
+                "Requested {:?} margin on {} but the gateway reports {:?}",
+                margin_mode, market_addr, echoed
+            ))),
+            _ => Ok(settings),
+        }
+    }
+
+    /// Get the maximum leverage of a market
+    pub async fn max_leverage(&self, market_addr: &str) -> Result<u32> {
+        if let Some(max_leverage) = self.max_leverage.read().await.get(market_addr) {
+            return Ok(*max_leverage);
+        }
+
+        let market = self
+            .client
+            .get_market_by_address(market_addr)
+            .await?
+            .ok_or_else(|| EkidenError::validation(format!("Unknown market {}", market_addr)))?;
+        self.max_leverage
+            .write()
+            .await
+            .insert(market_addr.to_string(), market.max_leverage);
+        Ok(market.max_leverage)
+    }
+
+    /// Create an order builder using the market's current leverage and margin mode.
+    ///
+    /// Fails if the gateway has not reported the margin mode of the market.
+    pub async fn order_builder(&self, market_addr: &str, side: OrderSide) -> Result<OrderBuilder> {
+        let settings = self.get(market_addr).await?;
+        let builder = OrderBuilder::new(market_addr, side).leverage(settings.leverage);
+        match settings.margin_mode {
+            Some(MarginMode::Cross) => Ok(builder.cross()),
+            Some(MarginMode::Isolated) => Ok(builder.isolated()),
+            None => Err(EkidenError::validation(format!(
+                "Margin mode of {} is unknown, set it with set_margin_mode",
+                market_addr
+            ))),
+        }
+    }
+
+    async fn validate_leverage(&self, market_addr: &str, leverage: u64) -> Result<()> {
+        let max_leverage = self.max_leverage(market_addr).await?;
+        validate_leverage(leverage, max_leverage)
+    }
+
+    async fn store(&self, market_addr: &str, response: LeverageResponse) -> MarketSettings {
+        let mut settings = self.settings.write().await;
+        let margin_mode = match response.is_cross {
+            Some(is_cross) => Some(MarginMode::from_is_cross(is_cross)),
+            None => settings.get(market_addr).and_then(|s| s.margin_mode),
+        };
+        let market_settings = MarketSettings {
+            leverage: response.leverage,
+            margin_mode,
+        };
+        settings.insert(market_addr.to_string(), market_settings);
+        market_settings
+    }
+}
+
+fn validate_leverage(leverage: u64, max_leverage: u32) -> Result<()> {
+    if leverage == 0 {
+        return Err(EkidenError::validation("Leverage must be at least 1"));
+    }
+    if leverage > max_leverage as u64 {
+        return Err(EkidenError::validation(format!(
+            "Leverage {} exceeds the market maximum of {}",
+            leverage, max_leverage
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_leverage() {
+        assert!(validate_leverage(0, 20).is_err());
+        assert!(validate_leverage(1, 20).is_ok());
+        assert!(validate_leverage(20, 20).is_ok());
+        assert!(validate_leverage(21, 20).is_err());
+    }
+
+    #[test]
+    fn test_margin_mode() {
+        assert_eq!(MarginMode::from_is_cross(true), MarginMode::Cross);
+        assert_eq!(MarginMode::from_is_cross(false), MarginMode::Isolated);
+        assert!(!MarginMode::Isolated.is_cross());
+    }
+
+    #[tokio::test]
+    async fn test_store_keeps_margin_mode() {
+        let settings = AccountSettings::new(EkidenClient::default_config().unwrap());
+        let response = |is_cross| LeverageResponse {
+            market_addr: "0x1".to_string(),
+            leverage: 5,
+            is_cross,
+        };
+
+        // Unknown until the gateway reports it
+        let stored = settings.store("0x2", response(None)).await;
+        assert_eq!(stored.margin_mode, None);
+        assert!(settings.order_builder("0x2", OrderSide::Buy).await.is_err());
+
+        let stored = settings.store("0x1", response(Some(false))).await;
+        assert_eq!(stored.margin_mode, Some(MarginMode::Isolated));
+
+        let stored = settings.store("0x1", response(None)).await;
+        assert_eq!(stored.margin_mode, Some(MarginMode::Isolated));
+        assert_eq!(settings.cached("0x1").await, Some(stored));
+
+        let builder = settings.order_builder("0x1", OrderSide::Buy).await.unwrap();
+        let order = builder.size(1).price(1).build().unwrap();
+        assert_eq!(order.leverage, 5);
+        assert!(!order.is_cross);
+    }
+}
@@ -0,0 +1,364 @@
+use crate::aptos::error::VaultError;
+use aptos_rust_sdk_types::api_types::{
+    address::AccountAddress,
+    module_id::ModuleId,
+    transaction::{EntryFunction, TransactionPayload},
+    type_tag::TypeTag,
+};
+use std::fmt::{Display, Formatter};
+
+/// Move type of an entry function argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveType {
+    Address,
+    Bool,
+    U8,
+    U64,
+    U128,
+    /// `vector<u8>`
+    Bytes,
+}
+
+impl Display for MoveType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MoveType::Address => "address",
+            MoveType::Bool => "bool",
+            MoveType::U8 => "u8",
+            MoveType::U64 => "u64",
+            MoveType::U128 => "u128",
+            MoveType::Bytes => "vector<u8>",
+        })
+    }
+}
+
+/// Rust value passed as a Move entry function argument
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveValue {
+    Address(AccountAddress),
+    Bool(bool),
+    U8(u8),
+    U64(u64),
+    U128(u128),
+    Bytes(Vec<u8>),
+}
+
+impl MoveValue {
+    /// Get the Move type of the value
+    pub fn move_type(&self) -> MoveType {
+        match self {
+            MoveValue::Address(_) => MoveType::Address,
+            MoveValue::Bool(_) => MoveType::Bool,
+            MoveValue::U8(_) => MoveType::U8,
+            MoveValue::U64(_) => MoveType::U64,
+            MoveValue::U128(_) => MoveType::U128,
+            MoveValue::Bytes(_) => MoveType::Bytes,
+        }
+    }
+
+    /// BCS-encode the value as an entry function argument
+    pub fn to_bcs(&self) -> Result<Vec<u8>, VaultError> {
+        Ok(match self {
+            MoveValue::Address(v) => bcs::to_bytes(v)?,
+            MoveValue::Bool(v) => bcs::to_bytes(v)?,
+            MoveValue::U8(v) => bcs::to_bytes(v)?,
+            MoveValue::U64(v) => bcs::to_bytes(v)?,
+            MoveValue::U128(v) => bcs::to_bytes(v)?,
+            MoveValue::Bytes(v) => bcs::to_bytes(v)?,
+        })
+    }
+}
+
+impl From<AccountAddress> for MoveValue {
+    fn from(value: AccountAddress) -> Self {
+        MoveValue::Address(value)
+    }
+}
+
+impl From<bool> for MoveValue {
+    fn from(value: bool) -> Self {
+        MoveValue::Bool(value)
+    }
+}
+
+impl From<u8> for MoveValue {
+    fn from(value: u8) -> Self {
+        MoveValue::U8(value)
+    }
+}
+
+impl From<u64> for MoveValue {
+    fn from(value: u64) -> Self {
+        MoveValue::U64(value)
+    }
+}
+
+impl From<u128> for MoveValue {
+    fn from(value: u128) -> Self {
+        MoveValue::U128(value)
+    }
+}
+
+impl From<Vec<u8>> for MoveValue {
+    fn from(value: Vec<u8>) -> Self {
+        MoveValue::Bytes(value)
+    }
+}
+
+/// Description of a Move entry function of the Ekiden contract.
+///
+/// New entry points only need a new constant in [`vault`], [`user`] or
+/// [`multisig_account`]. The Ekiden constants are checked against the
+/// published module ABI by the localnet tests, so add new ones there too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryFunctionSpec {
+    pub module: &'static str,
+    pub name: &'static str,
+    /// Names of the generic type parameters
+    pub type_params: &'static [&'static str],
+    /// Names and types of the arguments, without the leading `&signer`
+    pub args: &'static [(&'static str, MoveType)],
+}
+
+impl EntryFunctionSpec {
+    /// Fully qualified function id, e.g. `0x1::vault::withdraw_from_user`
+    pub fn function_id(&self, contract_addr: &AccountAddress) -> String {
+        format!("{}::{}::{}", contract_addr, self.module, self.name)
+    }
+
+    /// Check the arguments and build the entry function
+    pub fn build(
+        &self,
+        contract_addr: AccountAddress,
+        type_args: Vec<TypeTag>,
+        args: Vec<MoveValue>,
+    ) -> Result<EntryFunction, VaultError> {
+        if type_args.len() != self.type_params.len() {
+            return Err(VaultError::InvalidArgument(format!(
+                "{}::{} expects {} type arguments, got {}",
+                self.module,
+                self.name,
+                self.type_params.len(),
+                type_args.len()
+            )));
+        }
+        if args.len() != self.args.len() {
+            return Err(VaultError::InvalidArgument(format!(
+                "{}::{} expects {} arguments, got {}",
+                self.module,
+                self.name,
+                self.args.len(),
+                args.len()
+            )));
+        }
+
+        let mut encoded = Vec::with_capacity(args.len());
+        for ((name, expected), value) in self.args.iter().zip(&args) {
+            if value.move_type() != *expected {
+                return Err(VaultError::InvalidArgument(format!(
+                    "{}::{} argument {} must be {}, got {}",
+                    self.module,
+                    self.name,
+                    name,
+                    expected,
+                    value.move_type()
+                )));
+            }
+            encoded.push(value.to_bcs()?);
+        }
+
+        Ok(EntryFunction::new(
+            ModuleId::new(contract_addr, self.module.to_string()),
+            self.name.to_string(),
+            type_args,
+            encoded,
+        ))
+    }
+
+    /// Build a transaction payload for a function without type parameters
+    pub fn payload(
+        &self,
+        contract_addr: AccountAddress,
+        args: Vec<MoveValue>,
+    ) -> Result<TransactionPayload, VaultError> {
+        Ok(TransactionPayload::EntryFunction(self.build(
+            contract_addr,
+            vec![],
+            args,
+        )?))
+    }
+}
+
+/// Entry functions of the `vault` module
+pub mod vault {
+    use super::{EntryFunctionSpec, MoveType};
+
+    pub const DEPOSIT_INTO_FUNDING_WITH_TRANSFER_TO_CROSS_TRADING: EntryFunctionSpec =
+        EntryFunctionSpec {
+            module: "vault",
+            name: "deposit_into_funding_with_transfer_to_cross_trading",
+            type_params: &[],
+            args: &[
+                ("funding_addr", MoveType::Address),
+                ("trading_addr", MoveType::Address),
+                ("asset_addr", MoveType::Address),
+                ("amount", MoveType::U64),
+            ],
+        };
+
+    pub const WITHDRAW_FROM_USER: EntryFunctionSpec = EntryFunctionSpec {
+        module: "vault",
+        name: "withdraw_from_user",
+        type_params: &[],
+        args: &[("asset_addr", MoveType::Address), ("amount", MoveType::U64)],
+    };
+
+    pub const DEPOSIT_INTO_FUNDING: EntryFunctionSpec = EntryFunctionSpec {
+        module: "vault",
+        name: "deposit_into_funding",
+        type_params: &[],
+        args: &[
+            ("funding_addr", MoveType::Address),
+            ("asset_addr", MoveType::Address),
+            ("amount", MoveType::U64),
+        ],
+    };
+
+    pub const TRANSFER_FUNDING_TO_TRADING: EntryFunctionSpec = EntryFunctionSpec {
+        module: "vault",
+        name: "transfer_funding_to_trading",
+        type_params: &[],
+        args: &[("asset_addr", MoveType::Address), ("amount", MoveType::U64)],
+    };
+
+    pub const TRANSFER_TRADING_TO_FUNDING: EntryFunctionSpec = EntryFunctionSpec {
+        module: "vault",
+        name: "transfer_trading_to_funding",
+        type_params: &[],
+        args: &[("asset_addr", MoveType::Address), ("amount", MoveType::U64)],
+    };
+
+    pub const TRANSFER_CROSS_TO_ISOLATED: EntryFunctionSpec = EntryFunctionSpec {
+        module: "vault",
+        name: "transfer_cross_to_isolated",
+        type_params: &[],
+        args: &[
+            ("market_addr", MoveType::Address),
+            ("asset_addr", MoveType::Address),
+            ("amount", MoveType::U64),
+        ],
+    };
+
+    pub const WITHDRAW_TO: EntryFunctionSpec = EntryFunctionSpec {
+        module: "vault",
+        name: "withdraw_to",
+        type_params: &[],
+        args: &[
+            ("recipient", MoveType::Address),
+            ("asset_addr", MoveType::Address),
+            ("amount", MoveType::U64),
+        ],
+    };
+}
+
+/// Entry functions of the `user` module
+pub mod user {
+    use super::{EntryFunctionSpec, MoveType};
+
+    pub const CREATE_EKIDEN_USER: EntryFunctionSpec = EntryFunctionSpec {
+        module: "user",
+        name: "create_ekiden_user",
+        type_params: &[],
+        args: &[
+            ("funding_proof", MoveType::Bytes),
+            ("trading_proof", MoveType::Bytes),
+            ("asset_addr", MoveType::Address),
+        ],
+    };
+
+    pub const ROTATE_FUNDING_KEY: EntryFunctionSpec = EntryFunctionSpec {
+        module: "user",
+        name: "rotate_funding_key",
+        type_params: &[],
+        args: &[("proof", MoveType::Bytes)],
+    };
+
+    pub const ROTATE_TRADING_KEY: EntryFunctionSpec = EntryFunctionSpec {
+        module: "user",
+        name: "rotate_trading_key",
+        type_params: &[],
+        args: &[("proof", MoveType::Bytes)],
+    };
+}
+
+/// Entry functions of the framework's `multisig_account` module, at `0x1`
+pub mod multisig_account {
+    use super::{EntryFunctionSpec, MoveType};
+
+    pub const CREATE_TRANSACTION: EntryFunctionSpec = EntryFunctionSpec {
+        module: "multisig_account",
+        name: "create_transaction",
+        type_params: &[],
+        args: &[
+            ("multisig_account", MoveType::Address),
+            ("payload", MoveType::Bytes),
+        ],
+    };
+
+    pub const APPROVE_TRANSACTION: EntryFunctionSpec = EntryFunctionSpec {
+        module: "multisig_account",
+        name: "approve_transaction",
+        type_params: &[],
+        args: &[
+            ("multisig_account", MoveType::Address),
+            ("sequence_number", MoveType::U64),
+        ],
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_checks_arguments() {
+        let contract = AccountAddress::new([1u8; 32]);
+        let asset = AccountAddress::new([2u8; 32]);
+
+        assert!(vault::WITHDRAW_FROM_USER
+            .build(contract, vec![], vec![asset.into(), 10u64.into()])
+            .is_ok());
+        // Wrong count
+        assert!(vault::WITHDRAW_FROM_USER
+            .build(contract, vec![], vec![asset.into()])
+            .is_err());
+        // Wrong type
+        assert!(vault::WITHDRAW_FROM_USER
+            .build(contract, vec![], vec![asset.into(), 10u8.into()])
+            .is_err());
+        assert_eq!(
+            vault::WITHDRAW_FROM_USER.function_id(&contract),
+            format!("{}::vault::withdraw_from_user", contract)
+        );
+    }
+
+    #[test]
+    fn test_bcs_encoding() {
+        assert_eq!(
+            MoveValue::U64(1).to_bcs().unwrap(),
+            vec![1, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(MoveValue::Bool(true).to_bcs().unwrap(), vec![1]);
+        // vector<u8> is length-prefixed
+        assert_eq!(
+            MoveValue::Bytes(vec![0xaa, 0xbb]).to_bcs().unwrap(),
+            vec![2, 0xaa, 0xbb]
+        );
+        assert_eq!(
+            MoveValue::Address(AccountAddress::new([7u8; 32]))
+                .to_bcs()
+                .unwrap(),
+            vec![7u8; 32]
+        );
+    }
+}
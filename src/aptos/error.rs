@@ -0,0 +1,148 @@
+use crate::error::EkidenError;
+use aptos_rust_sdk_types::error::{AptosError, AptosErrorCode, RestError};
+use thiserror::Error;
+
+/// Errors returned by the vault contract helpers
+#[derive(Error, Debug)]
+pub enum VaultError {
+    #[error("Invalid address {address}: {reason}")]
+    InvalidAddress { address: String, reason: String },
+
+    #[error("Unknown network: {0}")]
+    InvalidNetwork(String),
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("Signing failed: {0}")]
+    Signing(String),
+
+    #[error("Transaction simulation failed: {0}")]
+    SimulationFailed(String),
+
+    #[error("Transaction {hash} aborted: {vm_status}")]
+    VmAbort { hash: String, vm_status: String },
+
+    #[error("Timed out waiting for transaction {0}")]
+    Timeout(String),
+
+    #[error("Sequence number error: {0}")]
+    SequenceNumber(String),
+
+    #[error("Account not found: {0}")]
+    AccountNotFound(String),
+
+    #[error("Unexpected response: {0}")]
+    InvalidResponse(String),
+
+    #[error("BCS serialization error: {0}")]
+    Bcs(#[from] bcs::Error),
+
+    #[error("Aptos REST error: {0:?}")]
+    Rest(RestError),
+}
+
+impl VaultError {
+    pub fn invalid_address<A: Into<String>, R: ToString>(address: A, reason: R) -> Self {
+        Self::InvalidAddress {
+            address: address.into(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Signing failure, keeping the signer's reason
+    pub fn signing<E: ToString>(error: E) -> Self {
+        Self::Signing(error.to_string())
+    }
+
+    pub fn invalid_response<S: Into<String>>(msg: S) -> Self {
+        Self::InvalidResponse(msg.into())
+    }
+
+    /// Whether the transaction may succeed if rebuilt and submitted again
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Timeout(_) | Self::SequenceNumber(_))
+    }
+}
+
+/// VM status codes of `SEQUENCE_NUMBER_TOO_OLD` and `SEQUENCE_NUMBER_TOO_NEW`
+const VM_SEQUENCE_NUMBER_TOO_OLD: u64 = 3;
+const VM_SEQUENCE_NUMBER_TOO_NEW: u64 = 4;
+
+impl From<RestError> for VaultError {
+    fn from(error: RestError) -> Self {
+        match &error {
+            RestError::Api(api) if is_sequence_number_error(api) => {
+                Self::SequenceNumber(api.message.clone())
+            }
+            RestError::Api(api) if matches!(api.error_code, AptosErrorCode::AccountNotFound) => {
+                Self::AccountNotFound(api.message.clone())
+            }
+            _ => Self::Rest(error),
+        }
+    }
+}
+
+/// The node rejects stale or reused sequence numbers on submission
+fn is_sequence_number_error(error: &AptosError) -> bool {
+    matches!(error.error_code, AptosErrorCode::SequenceNumberTooOld)
+        || matches!(
+            error.vm_error_code,
+            Some(VM_SEQUENCE_NUMBER_TOO_OLD | VM_SEQUENCE_NUMBER_TOO_NEW)
+        )
+}
+
+impl From<VaultError> for EkidenError {
+    fn from(error: VaultError) -> Self {
+        EkidenError::aptos(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vault_error_into_ekiden_error() {
+        let error: EkidenError = VaultError::VmAbort {
+            hash: "0xabc".to_string(),
+            vm_status: "Move abort: 0x1".to_string(),
+        }
+        .into();
+        assert!(matches!(error, EkidenError::Aptos(_)));
+        assert_eq!(
+            error.to_string(),
+            "Aptos error: Transaction 0xabc aborted: Move abort: 0x1"
+        );
+        assert!(VaultError::SequenceNumber("too old".to_string()).is_retryable());
+        assert!(!VaultError::InvalidNetwork("foo".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn test_rest_error_classification() {
+        let api = |error_code, vm_error_code| {
+            VaultError::from(RestError::Api(AptosError {
+                message: "rejected".to_string(),
+                error_code,
+                vm_error_code,
+            }))
+        };
+        assert!(matches!(
+            api(AptosErrorCode::SequenceNumberTooOld, None),
+            VaultError::SequenceNumber(_)
+        ));
+        assert!(matches!(
+            api(AptosErrorCode::VmError, Some(VM_SEQUENCE_NUMBER_TOO_NEW)),
+            VaultError::SequenceNumber(_)
+        ));
+        assert!(matches!(
+            api(AptosErrorCode::AccountNotFound, None),
+            VaultError::AccountNotFound(_)
+        ));
+        // Only the codes count, not the text of the message
+        assert!(matches!(
+            api(AptosErrorCode::VmError, Some(1)),
+            VaultError::Rest(_)
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+use crate::aptos::error::VaultError;
+use crate::aptos::vault::TransactionEvent;
+use aptos_rust_sdk_types::api_types::address::AccountAddress;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use std::str::FromStr;
+
+/// Assets deposited into the vault for a user
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositEvent {
+    #[serde_as(as = "DisplayFromStr")]
+    pub user: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub asset: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub amount: u64,
+}
+
+/// Assets withdrawn from the vault to a recipient
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawEvent {
+    #[serde_as(as = "DisplayFromStr")]
+    pub user: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub recipient: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub asset: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub amount: u64,
+}
+
+/// Assets moved between the funding and trading accounts of a user
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferEvent {
+    #[serde_as(as = "DisplayFromStr")]
+    pub from: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub to: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub asset: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub amount: u64,
+}
+
+/// Ekiden user created with its linked sub-accounts
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserCreatedEvent {
+    #[serde_as(as = "DisplayFromStr")]
+    pub root: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub funding: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub trading: AccountAddress,
+}
+
+/// Funding or trading account of a user replaced by a new key
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRotatedEvent {
+    #[serde_as(as = "DisplayFromStr")]
+    pub root: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub previous: AccountAddress,
+    #[serde_as(as = "DisplayFromStr")]
+    pub current: AccountAddress,
+}
+
+/// Event emitted by the Ekiden `vault` or `user` modules.
+///
+/// The struct layouts are checked against the published module ABI by the
+/// localnet tests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EkidenEvent {
+    Deposit(DepositEvent),
+    Withdraw(WithdrawEvent),
+    Transfer(TransferEvent),
+    UserCreated(UserCreatedEvent),
+    KeyRotated(KeyRotatedEvent),
+}
+
+impl EkidenEvent {
+    /// Decode an event emitted by the contract at `contract_addr`.
+    ///
+    /// Returns `None` for events of other modules or contracts, e.g. fee
+    /// statements and fungible asset transfers.
+    pub fn decode(
+        contract_addr: &AccountAddress,
+        event: &TransactionEvent,
+    ) -> Result<Option<Self>, VaultError> {
+        let mut parts = event.event_type.splitn(3, "::");
+        let (Some(address), Some(module), Some(name)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Ok(None);
+        };
+        if AccountAddress::from_str(address).ok().as_ref() != Some(contract_addr) {
+            return Ok(None);
+        }
+
+        let decoded = match (module, name) {
+            ("vault", "DepositEvent") => Self::Deposit(parse(event)?),
+            ("vault", "WithdrawEvent") => Self::Withdraw(parse(event)?),
+            ("vault", "TransferEvent") => Self::Transfer(parse(event)?),
+            ("user", "UserCreatedEvent") => Self::UserCreated(parse(event)?),
+            ("user", "KeyRotatedEvent") => Self::KeyRotated(parse(event)?),
+            _ => return Ok(None),
+        };
+        Ok(Some(decoded))
+    }
+
+    /// Decode the Ekiden events of a transaction or an account event stream,
+    /// skipping the others
+    pub fn decode_all(
+        contract_addr: &AccountAddress,
+        events: &[TransactionEvent],
+    ) -> Result<Vec<Self>, VaultError> {
+        let mut decoded = Vec::new();
+        for event in events {
+            if let Some(event) = Self::decode(contract_addr, event)? {
+                decoded.push(event);
+            }
+        }
+        Ok(decoded)
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(event: &TransactionEvent) -> Result<T, VaultError> {
+    serde_json::from_value(event.data.clone()).map_err(|e| {
+        VaultError::invalid_response(format!("Invalid {} event: {}", event.event_type, e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, data: serde_json::Value) -> TransactionEvent {
+        TransactionEvent::from_response(&serde_json::json!({
+            "sequence_number": "0",
+            "type": event_type,
+            "data": data,
+        }))
+    }
+
+    #[test]
+    fn test_decode_ekiden_events() {
+        let contract = AccountAddress::new([1u8; 32]);
+        let user = AccountAddress::new([2u8; 32]);
+        let asset = AccountAddress::new([3u8; 32]);
+        let events = vec![
+            event(
+                &format!("{}::vault::DepositEvent", contract),
+                serde_json::json!({
+                    "user": user.to_string(),
+                    "asset": asset.to_string(),
+                    "amount": "1000000",
+                }),
+            ),
+            event(
+                "0x1::transaction_fee::FeeStatement",
+                serde_json::json!({"total_charge_gas_units": "9"}),
+            ),
+            // Same module name under another contract
+            event(
+                &format!("{}::vault::DepositEvent", AccountAddress::new([9u8; 32])),
+                serde_json::json!({}),
+            ),
+        ];
+
+        let decoded = EkidenEvent::decode_all(&contract, &events).unwrap();
+        assert_eq!(
+            decoded,
+            vec![EkidenEvent::Deposit(DepositEvent {
+                user,
+                asset,
+                amount: 1_000_000,
+            })]
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed_event() {
+        let contract = AccountAddress::new([1u8; 32]);
+        let malformed = event(
+            &format!("{}::user::UserCreatedEvent", contract),
+            serde_json::json!({"root": "0x2"}),
+        );
+        assert!(EkidenEvent::decode(&contract, &malformed).is_err());
+    }
+}
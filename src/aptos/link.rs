@@ -0,0 +1,155 @@
+use crate::aptos::error::VaultError;
+use crate::signer::Signer;
+use aptos_crypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
+use aptos_crypto::Signature;
+use aptos_rust_sdk_types::api_types::{
+    address::AccountAddress, transaction_authenticator::AuthenticationKey,
+};
+
+/// Length of an encoded link proof: public key, root address and signature
+pub const LINK_PROOF_LEN: usize = 32 + 32 + 64;
+
+/// Proof that a funding or trading key belongs to a root account.
+///
+/// The sub-account key signs the root account address, and the contract
+/// checks the signature before linking the key to the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkProof {
+    pub public_key: Ed25519PublicKey,
+    pub root_address: AccountAddress,
+    pub signature: Ed25519Signature,
+}
+
+impl LinkProof {
+    /// Sign a link proof for `root_address` with the sub-account key
+    pub async fn build(
+        root_address: AccountAddress,
+        sub_account: &dyn Signer,
+    ) -> Result<Self, VaultError> {
+        let signature = sub_account
+            .sign_message(&root_address.to_bytes())
+            .await
+            .map_err(VaultError::signing)?;
+        Ok(Self {
+            public_key: sub_account.public_key(),
+            root_address,
+            signature,
+        })
+    }
+
+    /// Decode a proof from its on-chain byte layout
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VaultError> {
+        if bytes.len() != LINK_PROOF_LEN {
+            return Err(VaultError::invalid_response(format!(
+                "Link proof must be {} bytes",
+                LINK_PROOF_LEN
+            )));
+        }
+        let public_key = Ed25519PublicKey::try_from(&bytes[..32])
+            .map_err(|e| VaultError::invalid_response(format!("Invalid link proof key: {}", e)))?;
+        let mut root_address = [0u8; 32];
+        root_address.copy_from_slice(&bytes[32..64]);
+        let signature = Ed25519Signature::try_from(&bytes[64..]).map_err(|e| {
+            VaultError::invalid_response(format!("Invalid link proof signature: {}", e))
+        })?;
+        Ok(Self {
+            public_key,
+            root_address: AccountAddress::new(root_address),
+            signature,
+        })
+    }
+
+    /// Encode the proof as expected by the contract
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut proof = Vec::with_capacity(LINK_PROOF_LEN);
+        proof.extend(self.public_key.to_bytes());
+        proof.extend(self.root_address.to_bytes());
+        proof.extend(self.signature.to_bytes());
+        proof
+    }
+
+    /// Address of the linked sub-account
+    pub fn sub_account_address(&self) -> AccountAddress {
+        AuthenticationKey::ed25519(&self.public_key).account_address()
+    }
+
+    /// Check the signature and that the proof links to `root_address`
+    pub fn verify(&self, root_address: &AccountAddress) -> bool {
+        self.root_address == *root_address
+            && self
+                .signature
+                .verify_arbitrary_msg(&self.root_address.to_bytes(), &self.public_key)
+                .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{EkidenError, Result};
+    use crate::utils::KeyPair;
+    use async_trait::async_trait;
+
+    /// Signer whose signing service is unreachable
+    #[derive(Debug)]
+    struct UnreachableSigner(KeyPair);
+
+    #[async_trait]
+    impl Signer for UnreachableSigner {
+        fn public_key(&self) -> Ed25519PublicKey {
+            self.0.get_public_key()
+        }
+
+        async fn sign_message(&self, _message: &[u8]) -> Result<Ed25519Signature> {
+            Err(EkidenError::network("signing service unreachable"))
+        }
+    }
+
+    fn address(key_pair: &KeyPair) -> AccountAddress {
+        AuthenticationKey::ed25519(&key_pair.get_public_key()).account_address()
+    }
+
+    #[tokio::test]
+    async fn test_link_proof_roundtrip() {
+        let root = KeyPair::generate();
+        let trading = KeyPair::generate();
+        let proof = LinkProof::build(address(&root), &trading).await.unwrap();
+
+        assert!(proof.verify(&address(&root)));
+        assert_eq!(proof.sub_account_address(), address(&trading));
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), LINK_PROOF_LEN);
+        assert_eq!(LinkProof::from_bytes(&bytes).unwrap(), proof);
+        assert!(LinkProof::from_bytes(&bytes[1..]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_link_proof_rejects_other_root() {
+        let root = KeyPair::generate();
+        let other = KeyPair::generate();
+        let proof = LinkProof::build(address(&root), &KeyPair::generate())
+            .await
+            .unwrap();
+        assert!(!proof.verify(&address(&other)));
+
+        // A proof re-targeted at another root no longer matches its signature
+        let forged = LinkProof {
+            root_address: address(&other),
+            ..proof
+        };
+        assert!(!forged.verify(&address(&other)));
+    }
+
+    #[tokio::test]
+    async fn test_signing_error_keeps_cause() {
+        let root = KeyPair::generate();
+        let signer = UnreachableSigner(KeyPair::generate());
+        match LinkProof::build(address(&root), &signer).await {
+            Err(VaultError::Signing(reason)) => {
+                assert!(reason.contains("signing service unreachable"), "{}", reason)
+            }
+            other => panic!("Expected a signing error, got {:?}", other),
+        }
+    }
+}
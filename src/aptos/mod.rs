@@ -1 +1,8 @@
+pub mod entry;
+pub mod error;
+pub mod events;
+pub mod link;
+pub mod multisig;
+pub mod offline;
+pub mod sequence;
 pub mod vault;
@@ -0,0 +1,221 @@
+use crate::aptos::entry::{self, MoveValue};
+use crate::aptos::error::VaultError;
+use crate::signer::Signer;
+use aptos_crypto::ed25519::{Ed25519PublicKey, Ed25519Signature, ED25519_SIGNATURE_LENGTH};
+use aptos_crypto::multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature};
+use aptos_rust_sdk_types::api_types::{
+    address::AccountAddress,
+    transaction::{
+        EntryFunction, Multisig, MultisigTransactionPayload, RawTransaction, TransactionPayload,
+    },
+    transaction_authenticator::{AuthenticationKey, TransactionAuthenticator},
+};
+
+/// Address of the Aptos framework (`0x1`), home of `multisig_account`
+pub fn framework_address() -> AccountAddress {
+    let mut address = [0u8; 32];
+    address[31] = 1;
+    AccountAddress::new(address)
+}
+
+/// Account controlled by `threshold` of several Ed25519 keys (multi-ed25519)
+#[derive(Debug, Clone)]
+pub struct MultiEd25519Account {
+    public_key: MultiEd25519PublicKey,
+}
+
+impl MultiEd25519Account {
+    /// Create the account of `public_keys` requiring `threshold` signatures
+    pub fn new(public_keys: Vec<Ed25519PublicKey>, threshold: u8) -> Result<Self, VaultError> {
+        let public_key = MultiEd25519PublicKey::new(public_keys, threshold).map_err(|e| {
+            VaultError::InvalidArgument(format!("Invalid multi-ed25519 key: {}", e))
+        })?;
+        Ok(Self { public_key })
+    }
+
+    /// Get the multi-ed25519 public key
+    pub fn public_key(&self) -> &MultiEd25519PublicKey {
+        &self.public_key
+    }
+
+    /// Get the account address
+    pub fn address(&self) -> AccountAddress {
+        AuthenticationKey::multi_ed25519(&self.public_key).account_address()
+    }
+
+    /// Number of signatures needed
+    pub fn threshold(&self) -> u8 {
+        *self.public_key.threshold()
+    }
+
+    /// Position of `public_key` among the account's keys
+    fn key_index(&self, public_key: &Ed25519PublicKey) -> Result<u8, VaultError> {
+        self.public_key
+            .public_keys()
+            .iter()
+            .position(|key| key == public_key)
+            .map(|index| index as u8)
+            .ok_or_else(|| {
+                VaultError::InvalidArgument(format!(
+                    "Key 0x{} is not part of multi-ed25519 account {}",
+                    hex::encode(public_key.to_bytes()),
+                    self.address()
+                ))
+            })
+    }
+
+    /// Indices of the keys of `signers`, checking there are enough distinct
+    /// ones
+    fn signer_indices(&self, signers: &[&dyn Signer]) -> Result<Vec<u8>, VaultError> {
+        let mut indices = Vec::with_capacity(signers.len());
+        for signer in signers {
+            let index = self.key_index(&signer.public_key())?;
+            if indices.contains(&index) {
+                return Err(VaultError::InvalidArgument(format!(
+                    "Key {} signs more than once",
+                    index
+                )));
+            }
+            indices.push(index);
+        }
+        if indices.len() < self.threshold() as usize {
+            return Err(VaultError::InvalidArgument(format!(
+                "{} signatures given, {} required",
+                indices.len(),
+                self.threshold()
+            )));
+        }
+        Ok(indices)
+    }
+
+    /// Sign a raw transaction with each of `signers`
+    pub async fn sign(
+        &self,
+        raw_txn: &RawTransaction,
+        signers: &[&dyn Signer],
+    ) -> Result<TransactionAuthenticator, VaultError> {
+        let indices = self.signer_indices(signers)?;
+        let mut signatures = Vec::with_capacity(signers.len());
+        for (signer, index) in signers.iter().zip(indices) {
+            let signature = signer
+                .sign_transaction(raw_txn)
+                .await
+                .map_err(VaultError::signing)?;
+            signatures.push((signature, index));
+        }
+        self.authenticator(signatures)
+    }
+
+    /// Authenticator with placeholder signatures of `signers`, for simulation
+    pub(crate) fn simulation_authenticator(
+        &self,
+        signers: &[&dyn Signer],
+    ) -> Result<TransactionAuthenticator, VaultError> {
+        let signature = Ed25519Signature::try_from(&[0u8; ED25519_SIGNATURE_LENGTH][..])
+            .map_err(VaultError::signing)?;
+        let signatures = self
+            .signer_indices(signers)?
+            .into_iter()
+            .map(|index| (signature.clone(), index))
+            .collect();
+        self.authenticator(signatures)
+    }
+
+    fn authenticator(
+        &self,
+        signatures: Vec<(Ed25519Signature, u8)>,
+    ) -> Result<TransactionAuthenticator, VaultError> {
+        let signature = MultiEd25519Signature::new(signatures).map_err(VaultError::signing)?;
+        Ok(TransactionAuthenticator::multi_ed25519(
+            self.public_key.clone(),
+            signature,
+        ))
+    }
+}
+
+/// Payload proposing `function` as a transaction of an Aptos multisig
+/// account (`0x1::multisig_account::create_transaction`)
+pub fn multisig_proposal_payload(
+    multisig_addr: AccountAddress,
+    function: EntryFunction,
+) -> Result<TransactionPayload, VaultError> {
+    let proposal = bcs::to_bytes(&MultisigTransactionPayload::EntryFunction(function))?;
+    entry::multisig_account::CREATE_TRANSACTION.payload(
+        framework_address(),
+        vec![multisig_addr.into(), MoveValue::Bytes(proposal)],
+    )
+}
+
+/// Payload approving the pending multisig transaction `sequence_number`
+pub fn multisig_approval_payload(
+    multisig_addr: AccountAddress,
+    sequence_number: u64,
+) -> Result<TransactionPayload, VaultError> {
+    entry::multisig_account::APPROVE_TRANSACTION.payload(
+        framework_address(),
+        vec![multisig_addr.into(), sequence_number.into()],
+    )
+}
+
+/// Payload executing the next approved transaction of a multisig account.
+///
+/// `function` must match the proposal; it is executed as the multisig account.
+pub fn multisig_execution_payload(
+    multisig_addr: AccountAddress,
+    function: EntryFunction,
+) -> TransactionPayload {
+    TransactionPayload::Multisig(Multisig {
+        multisig_address: multisig_addr,
+        transaction_payload: Some(MultisigTransactionPayload::EntryFunction(function)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::KeyPair;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_multi_ed25519_signers() {
+        let keys: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate()).collect();
+        let outsider = KeyPair::generate();
+        let account =
+            MultiEd25519Account::new(keys.iter().map(|key| key.get_public_key()).collect(), 2)
+                .unwrap();
+        assert_eq!(account.threshold(), 2);
+        assert_ne!(
+            account.address(),
+            AuthenticationKey::ed25519(&keys[0].get_public_key()).account_address()
+        );
+
+        assert_eq!(
+            account.signer_indices(&[&keys[2], &keys[0]]).unwrap(),
+            vec![2, 0]
+        );
+        // Below threshold
+        assert!(account.signer_indices(&[&keys[1]]).is_err());
+        // Same key twice
+        assert!(account.signer_indices(&[&keys[1], &keys[1]]).is_err());
+        // Not a member
+        assert!(account.signer_indices(&[&keys[1], &outsider]).is_err());
+        assert!(account
+            .simulation_authenticator(&[&keys[0], &keys[1]])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_multisig_payloads() {
+        let multisig = AccountAddress::new([5u8; 32]);
+        assert_eq!(
+            framework_address(),
+            AccountAddress::from_str("0x1").unwrap()
+        );
+        assert!(multisig_approval_payload(multisig, 3).is_ok());
+
+        let function = entry::vault::WITHDRAW_FROM_USER
+            .build(multisig, vec![], vec![multisig.into(), 1u64.into()])
+            .unwrap();
+        assert!(multisig_proposal_payload(multisig, function).is_ok());
+    }
+}
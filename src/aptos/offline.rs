@@ -0,0 +1,137 @@
+use crate::aptos::error::VaultError;
+use crate::aptos::vault::chain_id_from_u8;
+use crate::signer::Signer;
+use aptos_rust_sdk_types::api_types::{
+    address::AccountAddress,
+    transaction::{RawTransaction, SignedTransaction, TransactionPayload},
+    transaction_authenticator::TransactionAuthenticator,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Inputs of a transaction that are otherwise read from the chain, so it
+/// can be built and signed without network access.
+///
+/// Fetch them on an online machine with
+/// [`VaultContract::transaction_params`](crate::aptos::vault::VaultContract::transaction_params).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionParams {
+    pub sequence_number: u64,
+    /// Unix time after which the transaction can no longer be committed
+    pub expiration_timestamp_secs: u64,
+    pub chain_id: u8,
+    pub max_gas_amount: u64,
+    pub gas_unit_price: u64,
+}
+
+impl TransactionParams {
+    /// Build a raw transaction of `sender` from these parameters
+    pub fn build(&self, sender: AccountAddress, payload: TransactionPayload) -> RawTransaction {
+        RawTransaction::new(
+            sender,
+            self.sequence_number,
+            payload,
+            self.max_gas_amount,
+            self.gas_unit_price,
+            self.expiration_timestamp_secs,
+            chain_id_from_u8(self.chain_id),
+        )
+    }
+}
+
+/// Sign a raw transaction, without any network access
+pub async fn sign_offline(
+    raw_txn: RawTransaction,
+    signer: &dyn Signer,
+) -> Result<SignedTransaction, VaultError> {
+    let signature = signer
+        .sign_transaction(&raw_txn)
+        .await
+        .map_err(VaultError::signing)?;
+    Ok(SignedTransaction::new(
+        raw_txn,
+        TransactionAuthenticator::ed25519(signer.public_key(), signature),
+    ))
+}
+
+/// Export a raw transaction as hex-encoded BCS
+pub fn export_raw_txn(raw_txn: &RawTransaction) -> Result<String, VaultError> {
+    to_hex(raw_txn)
+}
+
+/// Import a raw transaction exported with [`export_raw_txn`]
+pub fn import_raw_txn(hex: &str) -> Result<RawTransaction, VaultError> {
+    from_hex(hex)
+}
+
+/// Export a signed transaction as hex-encoded BCS
+pub fn export_signed_txn(signed_txn: &SignedTransaction) -> Result<String, VaultError> {
+    to_hex(signed_txn)
+}
+
+/// Import a signed transaction exported with [`export_signed_txn`]
+pub fn import_signed_txn(hex: &str) -> Result<SignedTransaction, VaultError> {
+    from_hex(hex)
+}
+
+fn to_hex<T: Serialize>(value: &T) -> Result<String, VaultError> {
+    Ok(format!("0x{}", hex::encode(bcs::to_bytes(value)?)))
+}
+
+fn from_hex<T: DeserializeOwned>(value: &str) -> Result<T, VaultError> {
+    let bytes = hex::decode(value.trim().trim_start_matches("0x"))
+        .map_err(|e| VaultError::InvalidArgument(format!("Invalid transaction hex: {}", e)))?;
+    Ok(bcs::from_bytes(&bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aptos::entry;
+    use crate::utils::KeyPair;
+    use aptos_rust_sdk_types::api_types::transaction_authenticator::AuthenticationKey;
+
+    fn params() -> TransactionParams {
+        TransactionParams {
+            sequence_number: 5,
+            expiration_timestamp_secs: 1_700_000_600,
+            chain_id: 2,
+            max_gas_amount: 2_000,
+            gas_unit_price: 100,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_offline_roundtrip() {
+        let key_pair = KeyPair::generate();
+        let sender = AuthenticationKey::ed25519(&key_pair.get_public_key()).account_address();
+        let contract = AccountAddress::new([1u8; 32]);
+        let payload = entry::vault::WITHDRAW_FROM_USER
+            .payload(
+                contract,
+                vec![AccountAddress::new([2u8; 32]).into(), 10u64.into()],
+            )
+            .unwrap();
+
+        // Online: build and export
+        let exported = export_raw_txn(&params().build(sender, payload)).unwrap();
+        assert!(exported.starts_with("0x"));
+
+        // Offline: import, sign and export
+        let raw_txn = import_raw_txn(&exported).unwrap();
+        assert_eq!(export_raw_txn(&raw_txn).unwrap(), exported);
+        let signed = sign_offline(raw_txn, &key_pair).await.unwrap();
+        let signed_hex = export_signed_txn(&signed).unwrap();
+
+        // Online: import for submission
+        let imported = import_signed_txn(&signed_hex).unwrap();
+        assert_eq!(export_signed_txn(&imported).unwrap(), signed_hex);
+        // The signed transaction starts with the raw transaction
+        assert!(signed_hex.starts_with(&exported));
+    }
+
+    #[test]
+    fn test_import_rejects_bad_hex() {
+        assert!(import_raw_txn("0xzz").is_err());
+        assert!(import_signed_txn("0x00").is_err());
+    }
+}
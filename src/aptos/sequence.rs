@@ -0,0 +1,124 @@
+use crate::aptos::error::VaultError;
+use aptos_rust_sdk_types::api_types::address::AccountAddress;
+use std::collections::HashMap;
+use std::future::Future;
+use tokio::sync::Mutex;
+
+/// Allocates sequence numbers per account without re-reading them from the
+/// chain for every transaction.
+///
+/// The first allocation for an account fetches its on-chain sequence number;
+/// later ones increment locally, so several transactions can be in flight at
+/// once. After a rejected submission the account is reset and fetched again.
+#[derive(Debug, Default)]
+pub struct SequenceNumbers {
+    accounts: Mutex<HashMap<AccountAddress, u64>>,
+}
+
+impl SequenceNumbers {
+    /// Create an empty allocator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocate the next sequence number of `account`, calling `fetch` to read
+    /// the on-chain value if it is not known yet
+    pub async fn next<F, Fut>(&self, account: AccountAddress, fetch: F) -> Result<u64, VaultError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<u64, VaultError>>,
+    {
+        // Hold the lock while fetching, so concurrent first allocations agree
+        let mut accounts = self.accounts.lock().await;
+        let next = match accounts.get(&account) {
+            Some(next) => *next,
+            None => fetch().await?,
+        };
+        accounts.insert(account, next + 1);
+        Ok(next)
+    }
+
+    /// Get the next sequence number that would be allocated, if known
+    pub async fn peek(&self, account: &AccountAddress) -> Option<u64> {
+        self.accounts.lock().await.get(account).copied()
+    }
+
+    /// Set the next sequence number of `account`
+    pub async fn set(&self, account: AccountAddress, next: u64) {
+        self.accounts.lock().await.insert(account, next);
+    }
+
+    /// Forget the local state of `account`, so the next allocation resyncs
+    pub async fn reset(&self, account: &AccountAddress) {
+        self.accounts.lock().await.remove(account);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_fetches_once_then_increments() {
+        let sequence_numbers = Arc::new(SequenceNumbers::new());
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let account = AccountAddress::new([1u8; 32]);
+
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let sequence_numbers = sequence_numbers.clone();
+                let fetches = fetches.clone();
+                tokio::spawn(async move {
+                    sequence_numbers
+                        .next(account, || async {
+                            fetches.fetch_add(1, Ordering::SeqCst);
+                            Ok(7)
+                        })
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+        let mut allocated = Vec::new();
+        for handle in handles {
+            allocated.push(handle.await.unwrap());
+        }
+        allocated.sort();
+
+        assert_eq!(allocated, vec![7, 8, 9, 10, 11]);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(sequence_numbers.peek(&account).await, Some(12));
+    }
+
+    #[tokio::test]
+    async fn test_reset_resyncs() {
+        let sequence_numbers = SequenceNumbers::new();
+        let account = AccountAddress::new([2u8; 32]);
+        assert_eq!(
+            sequence_numbers
+                .next(account, || async { Ok(3) })
+                .await
+                .unwrap(),
+            3
+        );
+
+        sequence_numbers.reset(&account).await;
+        assert_eq!(sequence_numbers.peek(&account).await, None);
+        assert_eq!(
+            sequence_numbers
+                .next(account, || async { Ok(10) })
+                .await
+                .unwrap(),
+            10
+        );
+
+        let failed = sequence_numbers
+            .next(AccountAddress::new([3u8; 32]), || async {
+                Err(VaultError::SequenceNumber("no account".to_string()))
+            })
+            .await;
+        assert!(failed.is_err());
+    }
+}
@@ -1,31 +1,55 @@
-use crate::KeyPair;
-use aptos_crypto::{ed25519, HashValue, SigningKey, ValidCryptoMaterialStringExt};
+use crate::aptos::entry::{self, EntryFunctionSpec, MoveValue};
+use crate::aptos::error::VaultError;
+use crate::aptos::events::EkidenEvent;
+use crate::aptos::link::LinkProof;
+use crate::aptos::multisig::{
+    multisig_approval_payload, multisig_execution_payload, multisig_proposal_payload,
+    MultiEd25519Account,
+};
+use crate::aptos::offline::TransactionParams;
+use crate::aptos::sequence::SequenceNumbers;
+use crate::keystore::KeyRole;
+use crate::signer::Signer;
+use aptos_crypto::ed25519::{self, Ed25519PublicKey, ED25519_SIGNATURE_LENGTH};
 use aptos_rust_sdk::client::{
     builder::AptosClientBuilder, config::AptosNetwork, rest_api::AptosFullnodeClient,
 };
-use aptos_rust_sdk_types::{
-    api_types::{
-        address::AccountAddress,
-        chain_id::ChainId,
-        module_id::ModuleId,
-        transaction::{EntryFunction, RawTransaction, SignedTransaction, TransactionPayload},
-        transaction_authenticator::{AuthenticationKey, TransactionAuthenticator},
+use aptos_rust_sdk_types::api_types::{
+    address::AccountAddress,
+    chain_id::ChainId,
+    transaction::{EntryFunction, RawTransaction, SignedTransaction, TransactionPayload},
+    transaction_authenticator::{
+        AccountAuthenticator, AuthenticationKey, TransactionAuthenticator,
     },
-    error::RestError,
+    view::ViewRequest,
 };
 use serde::{Deserialize, Serialize};
 use std::{str::FromStr, time::Duration};
+use tokio::sync::OnceCell;
 use tokio::time::Instant;
+use tracing::{debug, info, warn};
+use url::Url;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VaultId {
     pub inner: String,
 }
 
+/// Sub-accounts linked to an Ekiden root account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkedAccounts {
+    pub funding: AccountAddress,
+    pub trading: AccountAddress,
+}
+
 pub struct VaultContract {
     pub client: AptosFullnodeClient,
     pub contract_addr: AccountAddress,
     pub asset_addr: AccountAddress,
+    chain_id: OnceCell<u8>,
+    gas: GasConfig,
+    poll: PollBackoff,
+    sequence_numbers: SequenceNumbers,
 }
 #[derive(Debug)]
 pub enum TransactionStatus {
@@ -34,79 +58,393 @@ pub enum TransactionStatus {
     Failed(String),
 }
 
+/// How gas is sized for submitted transactions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasConfig {
+    /// Multiplier applied to the simulated gas usage
+    pub multiplier: f64,
+    /// Upper bound of `max_gas_amount`, also used when simulating
+    pub max_gas_amount: u64,
+    /// Fixed gas unit price; the node's estimate is used if unset
+    pub gas_unit_price: Option<u64>,
+}
+
+impl Default for GasConfig {
+    fn default() -> Self {
+        Self {
+            multiplier: 1.5,
+            max_gas_amount: 200_000,
+            gas_unit_price: None,
+        }
+    }
+}
+
+impl GasConfig {
+    /// Size `max_gas_amount` from the simulated gas usage
+    pub fn max_gas_for(&self, gas_used: u64) -> u64 {
+        let max_gas = (gas_used as f64 * self.multiplier.max(1.0)).ceil() as u64;
+        max_gas.clamp(1, self.max_gas_amount)
+    }
+}
+
+/// Outcome of a simulated transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulationResult {
+    pub success: bool,
+    pub vm_status: String,
+    pub gas_used: u64,
+    pub gas_unit_price: u64,
+}
+
+impl SimulationResult {
+    /// Parse the node's simulation response
+    pub fn from_response(response: &serde_json::Value) -> Result<Self, VaultError> {
+        // The node returns one result per simulated transaction
+        let txn = match response {
+            serde_json::Value::Array(txns) => txns
+                .first()
+                .ok_or_else(|| VaultError::invalid_response("Empty simulation response"))?,
+            txn => txn,
+        };
+        let number = |field: &str| {
+            txn.get(field)
+                .and_then(|v| v.as_str())
+                .and_then(|v| v.parse::<u64>().ok())
+                .ok_or_else(|| VaultError::invalid_response(format!("Missing {}", field)))
+        };
+        Ok(Self {
+            success: txn
+                .get("success")
+                .and_then(|v| v.as_bool())
+                .ok_or_else(|| VaultError::invalid_response("Missing success"))?,
+            vm_status: txn
+                .get("vm_status")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            gas_used: number("gas_used")?,
+            gas_unit_price: number("gas_unit_price")?,
+        })
+    }
+}
+
+/// Committed transaction with its on-chain version and gas usage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionReceipt {
+    pub hash: String,
+    pub version: u64,
+    pub gas_used: u64,
+    pub success: bool,
+    pub vm_status: String,
+}
+
+impl TransactionReceipt {
+    /// Parse a committed transaction returned by the node
+    pub fn from_response(response: &serde_json::Value) -> Result<Self, VaultError> {
+        let string = |field: &str| {
+            response
+                .get(field)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| VaultError::invalid_response(format!("Missing {}", field)))
+        };
+        let number = |field: &str| {
+            string(field)?
+                .parse::<u64>()
+                .map_err(|e| VaultError::invalid_response(format!("Invalid {}: {}", field, e)))
+        };
+        Ok(Self {
+            hash: string("hash")?.to_string(),
+            version: number("version")?,
+            gas_used: number("gas_used")?,
+            success: response
+                .get("success")
+                .and_then(|v| v.as_bool())
+                .ok_or_else(|| VaultError::invalid_response("Missing success"))?,
+            vm_status: response
+                .get("vm_status")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+}
+
+/// Event emitted by a committed transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionEvent {
+    /// Move type of the event, e.g. `0x1::fungible_asset::Deposit`
+    pub event_type: String,
+    pub sequence_number: u64,
+    pub data: serde_json::Value,
+}
+
+impl TransactionEvent {
+    /// Parse an event as returned by the node, in a transaction or from an
+    /// account's event stream
+    pub fn from_response(event: &serde_json::Value) -> Self {
+        Self {
+            event_type: event
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            sequence_number: event
+                .get("sequence_number")
+                .and_then(|v| v.as_str())
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or_default(),
+            data: event.get("data").cloned().unwrap_or_default(),
+        }
+    }
+}
+
+/// Full outcome of a committed transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionOutcome {
+    pub hash: String,
+    pub version: u64,
+    pub gas_used: u64,
+    pub success: bool,
+    pub vm_status: String,
+    pub events: Vec<TransactionEvent>,
+    /// Block timestamp in microseconds
+    pub timestamp_usecs: u64,
+}
+
+impl TransactionOutcome {
+    /// Parse a committed transaction returned by the node
+    pub fn from_response(response: &serde_json::Value) -> Result<Self, VaultError> {
+        let TransactionReceipt {
+            hash,
+            version,
+            gas_used,
+            success,
+            vm_status,
+        } = TransactionReceipt::from_response(response)?;
+        let timestamp_usecs = response
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .and_then(|v| v.parse::<u64>().ok())
+            .ok_or_else(|| VaultError::invalid_response("Missing timestamp"))?;
+        let events = response
+            .get("events")
+            .and_then(|v| v.as_array())
+            .map(|events| events.iter().map(TransactionEvent::from_response).collect())
+            .unwrap_or_default();
+        Ok(Self {
+            hash,
+            version,
+            gas_used,
+            success,
+            vm_status,
+            events,
+            timestamp_usecs,
+        })
+    }
+
+    /// Fail with [`VaultError::VmAbort`] if the transaction aborted
+    pub fn into_result(self) -> Result<Self, VaultError> {
+        if self.success {
+            Ok(self)
+        } else {
+            Err(VaultError::VmAbort {
+                hash: self.hash,
+                vm_status: self.vm_status,
+            })
+        }
+    }
+
+    /// Summary of the outcome without events
+    pub fn receipt(&self) -> TransactionReceipt {
+        TransactionReceipt {
+            hash: self.hash.clone(),
+            version: self.version,
+            gas_used: self.gas_used,
+            success: self.success,
+            vm_status: self.vm_status.clone(),
+        }
+    }
+}
+
+/// Exponential backoff used to poll the node for pending transactions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PollBackoff {
+    /// Delay before the second poll
+    pub initial: Duration,
+    /// Upper bound of the delay
+    pub max: Duration,
+    /// Factor applied to the delay after each poll
+    pub multiplier: f64,
+}
+
+impl Default for PollBackoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(200),
+            max: Duration::from_secs(5),
+            multiplier: 1.5,
+        }
+    }
+}
+
+impl PollBackoff {
+    /// Get the delay following `delay`
+    pub fn next(&self, delay: Duration) -> Duration {
+        delay.mul_f64(self.multiplier.max(1.0)).min(self.max)
+    }
+}
+
+/// How long the vault operations wait for their transaction to commit
+pub const DEFAULT_TRANSACTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// REST API of a node started with `aptos node run-localnet`
+pub const LOCALNET_URL: &str = "http://127.0.0.1:8080/v1";
+
 impl VaultContract {
-    pub fn new(contract_addr: &str, asset_addr: &str, network: &str) -> Self {
-        let network = match network {
-            "mainnet" => AptosNetwork::mainnet(),
-            "testnet" => AptosNetwork::testnet(),
-            "devnet" => AptosNetwork::devnet(),
-            _ => {
-                panic!("INVALID_NETWORK")
-            }
+    /// Connect to a named network: "mainnet", "testnet", "devnet" or "localnet"
+    pub fn new(contract_addr: &str, asset_addr: &str, network: &str) -> Result<Self, VaultError> {
+        let (network, chain_id) = match network {
+            "mainnet" => (AptosNetwork::mainnet(), Some(1)),
+            "testnet" => (AptosNetwork::testnet(), Some(2)),
+            // Devnet and localnet chain ids change on every reset
+            "devnet" => (AptosNetwork::devnet(), None),
+            "localnet" => (custom_network(LOCALNET_URL)?, None),
+            _ => return Err(VaultError::InvalidNetwork(network.to_string())),
         };
+        Self::with_network(contract_addr, asset_addr, network, chain_id)
+    }
 
+    /// Connect to an arbitrary fullnode REST URL, e.g. a private node or localnet.
+    ///
+    /// Without a chain id, it is read from the node's ledger info on first use.
+    pub fn with_node_url(
+        contract_addr: &str,
+        asset_addr: &str,
+        node_url: &str,
+        chain_id: Option<u8>,
+    ) -> Result<Self, VaultError> {
+        Self::with_network(
+            contract_addr,
+            asset_addr,
+            custom_network(node_url)?,
+            chain_id,
+        )
+    }
+
+    fn with_network(
+        contract_addr: &str,
+        asset_addr: &str,
+        network: AptosNetwork,
+        chain_id: Option<u8>,
+    ) -> Result<Self, VaultError> {
         let client = AptosClientBuilder::new(network).build();
-        let contract_addr = AccountAddress::from_str(contract_addr).unwrap();
-        let asset_addr = AccountAddress::from_str(asset_addr).unwrap();
+        let contract_addr = parse_address(contract_addr)?;
+        let asset_addr = parse_address(asset_addr)?;
 
-        Self {
+        Ok(Self {
             client,
             contract_addr,
             asset_addr,
-        }
+            chain_id: OnceCell::new_with(chain_id),
+            gas: GasConfig::default(),
+            poll: PollBackoff::default(),
+            sequence_numbers: SequenceNumbers::new(),
+        })
     }
 
-    pub async fn get_sequence_number(&self, sender: &AccountAddress) -> Result<u64, RestError> {
+    /// Read the on-chain sequence number of an account
+    pub async fn get_sequence_number(&self, sender: &AccountAddress) -> Result<u64, VaultError> {
         let resource = self
             .client
             .get_account_resources(sender.to_string())
             .await?
             .into_inner();
 
-        let sequence_number = resource
+        resource
             .iter()
             .find(|r| r.type_ == "0x1::account::Account")
             .and_then(|r| r.data.get("sequence_number"))
             .and_then(|v| v.as_str())
             .and_then(|v| v.parse::<u64>().ok())
-            .unwrap_or(2);
+            .ok_or_else(|| VaultError::AccountNotFound(sender.to_string()))
+    }
 
-        Ok(sequence_number)
+    /// Get the chain id, reading it from the node's ledger info if unknown
+    pub async fn get_chain_id(&self) -> Result<ChainId, VaultError> {
+        let chain_id = self
+            .chain_id
+            .get_or_try_init(|| async {
+                let state = self.client.get_state().await?;
+                Ok::<_, VaultError>(state.chain_id)
+            })
+            .await?;
+        Ok(chain_id_from_u8(*chain_id))
     }
 
-    pub fn get_chain_id(&self) -> ChainId {
-        let chain_id = match self.client.network().name() {
-            "mainnet" => ChainId::Mainnet,
-            "testnet" => ChainId::Testnet,
-            "devnet" => ChainId::Other(0),
-            _ => {
-                panic!("INVALID_NETWORK")
-            }
-        };
+    /// Set how the node is polled while waiting for transactions
+    pub fn with_poll_backoff(mut self, poll: PollBackoff) -> Self {
+        self.poll = poll;
+        self
+    }
 
-        chain_id
+    /// Set how gas is sized for submitted transactions
+    pub fn with_gas_config(mut self, gas: GasConfig) -> Self {
+        self.gas = gas;
+        self
     }
 
+    /// Get the node's gas unit price estimate
+    pub async fn estimate_gas_price(&self) -> Result<u64, VaultError> {
+        let estimate = self.client.get_estimate_gas_price().await?.into_inner();
+        estimate
+            .get("gas_estimate")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| VaultError::invalid_response("Missing gas estimate"))
+    }
+
+    async fn gas_unit_price(&self) -> Result<u64, VaultError> {
+        match self.gas.gas_unit_price {
+            Some(price) => Ok(price),
+            None => self.estimate_gas_price().await,
+        }
+    }
+
+    /// Build a transaction with the estimated gas price and the configured
+    /// gas limit
     pub async fn build_raw_txn(
         &self,
         payload: TransactionPayload,
         sender: AccountAddress,
         sequence_number_option: Option<u64>,
-    ) -> Result<RawTransaction, RestError> {
+    ) -> Result<RawTransaction, VaultError> {
+        let gas_unit_price = self.gas_unit_price().await?;
+        self.build_raw_txn_with_gas(
+            payload,
+            sender,
+            sequence_number_option,
+            self.gas.max_gas_amount,
+            gas_unit_price,
+        )
+        .await
+    }
+
+    async fn build_raw_txn_with_gas(
+        &self,
+        payload: TransactionPayload,
+        sender: AccountAddress,
+        sequence_number_option: Option<u64>,
+        max_gas_amount: u64,
+        gas_unit_price: u64,
+    ) -> Result<RawTransaction, VaultError> {
         let state = self.client.get_state().await?;
 
-        let max_gas_amount = 10000;
-        let gas_unit_price = 100;
         let expiration_timestamp_secs = state.timestamp_usecs / 1000 / 1000 + 60 * 10;
-        let sequence_number;
-
-        if let Some(number) = sequence_number_option {
-            sequence_number = number;
-        } else {
-            sequence_number = self.get_sequence_number(&sender).await?;
+        let sequence_number = match sequence_number_option {
+            Some(number) => number,
+            None => self.get_sequence_number(&sender).await?,
         };
 
-        let chain_id = self.get_chain_id();
+        let chain_id = self.get_chain_id().await?;
 
         let raw_txn = RawTransaction::new(
             sender,
@@ -121,241 +459,1175 @@ impl VaultContract {
         Ok(raw_txn)
     }
 
-    pub fn sign_txn(
+    /// Simulate a transaction from the account of `public_key` without
+    /// submitting it.
+    ///
+    /// No signature is needed: the node rejects simulations carrying a valid one.
+    pub async fn simulate(
+        &self,
+        payload: TransactionPayload,
+        public_key: &Ed25519PublicKey,
+        sequence_number_option: Option<u64>,
+    ) -> Result<SimulationResult, VaultError> {
+        self.simulate_as(payload, public_key, None, sequence_number_option)
+            .await
+    }
+
+    /// Simulate a transaction from the account of `public_key` whose gas is
+    /// paid by the account of `fee_payer`
+    pub async fn simulate_sponsored(
+        &self,
+        payload: TransactionPayload,
+        public_key: &Ed25519PublicKey,
+        fee_payer: &Ed25519PublicKey,
+        sequence_number_option: Option<u64>,
+    ) -> Result<SimulationResult, VaultError> {
+        self.simulate_as(payload, public_key, Some(fee_payer), sequence_number_option)
+            .await
+    }
+
+    async fn simulate_as(
+        &self,
+        payload: TransactionPayload,
+        public_key: &Ed25519PublicKey,
+        fee_payer: Option<&Ed25519PublicKey>,
+        sequence_number_option: Option<u64>,
+    ) -> Result<SimulationResult, VaultError> {
+        let sender = AuthenticationKey::ed25519(public_key).account_address();
+        let raw_txn = self
+            .build_raw_txn(payload, sender, sequence_number_option)
+            .await?;
+        let signature = ed25519::Signature::try_from(&[0u8; ED25519_SIGNATURE_LENGTH][..])
+            .map_err(VaultError::signing)?;
+        let fee_payer = fee_payer.map(|key| (key.clone(), signature.clone()));
+        let resp = self
+            .client
+            .simulate_transaction(SignedTransaction::new(
+                raw_txn,
+                authenticator(public_key.clone(), signature, fee_payer),
+            ))
+            .await?;
+        SimulationResult::from_response(resp.inner())
+    }
+
+    /// Sign a raw transaction. No network access is needed, see
+    /// [`sign_offline`](crate::aptos::offline::sign_offline) for air-gapped
+    /// signing.
+    pub async fn sign_txn(
         &self,
         raw_txn: &RawTransaction,
-        signer: ed25519::PrivateKey,
-    ) -> ed25519::Signature {
-        let hash = HashValue::sha3_256_of("APTOS::RawTransaction".as_bytes());
-        let mut bytes = vec![];
-        bcs::serialize_into(&mut bytes, raw_txn).unwrap();
+        signer: &dyn Signer,
+    ) -> Result<ed25519::Signature, VaultError> {
+        signer
+            .sign_transaction(raw_txn)
+            .await
+            .map_err(VaultError::signing)
+    }
 
-        let mut message = vec![];
-        message.extend(hash.to_vec());
-        message.extend(bytes);
+    /// Allocate the next sequence number of `sender` from the local allocator
+    pub async fn next_sequence_number(&self, sender: AccountAddress) -> Result<u64, VaultError> {
+        self.sequence_numbers
+            .next(sender, || self.get_sequence_number(&sender))
+            .await
+    }
 
-        signer.sign_message(&message)
+    /// Allocate a sequence number; a sponsored sender without an account
+    /// starts at 0
+    async fn allocate_sequence_number(
+        &self,
+        sender: AccountAddress,
+        sponsored: bool,
+    ) -> Result<u64, VaultError> {
+        if !sponsored {
+            return self.next_sequence_number(sender).await;
+        }
+        self.sequence_numbers
+            .next(sender, || async {
+                match self.get_sequence_number(&sender).await {
+                    Err(VaultError::AccountNotFound(_)) => Ok(0),
+                    result => result,
+                }
+            })
+            .await
+    }
+
+    /// Get the local sequence number allocator
+    pub fn sequence_numbers(&self) -> &SequenceNumbers {
+        &self.sequence_numbers
     }
 
+    /// Simulate, sign and submit a transaction.
+    ///
+    /// Without an explicit sequence number one is allocated locally; if the
+    /// node rejects it as too old or too new, the account is resynced from
+    /// the chain and the transaction retried once.
     pub async fn submit(
         &self,
         payload: TransactionPayload,
-        signer: ed25519::PrivateKey,
+        signer: &dyn Signer,
         sequence_number_option: Option<u64>,
-    ) -> Result<String, RestError> {
-        let public_key = ed25519::PublicKey::from(&signer);
+    ) -> Result<String, VaultError> {
+        self.submit_as(payload, signer, None, sequence_number_option)
+            .await
+    }
+
+    /// Simulate, sign and submit a transaction whose gas is paid by
+    /// `fee_payer`, so the sender needs no APT.
+    ///
+    /// The sender's account may not exist yet; it is then created by the
+    /// transaction, starting at sequence number 0.
+    pub async fn submit_sponsored(
+        &self,
+        payload: TransactionPayload,
+        signer: &dyn Signer,
+        fee_payer: &dyn Signer,
+        sequence_number_option: Option<u64>,
+    ) -> Result<String, VaultError> {
+        self.submit_as(payload, signer, Some(fee_payer), sequence_number_option)
+            .await
+    }
+
+    async fn submit_as(
+        &self,
+        payload: TransactionPayload,
+        signer: &dyn Signer,
+        fee_payer: Option<&dyn Signer>,
+        sequence_number_option: Option<u64>,
+    ) -> Result<String, VaultError> {
+        if let Some(sequence_number) = sequence_number_option {
+            return self
+                .submit_with_sequence_number(payload, signer, fee_payer, sequence_number, true)
+                .await;
+        }
+
+        let sender = AuthenticationKey::ed25519(&signer.public_key()).account_address();
+        let sponsored = fee_payer.is_some();
+        let sequence_number = self.allocate_sequence_number(sender, sponsored).await?;
+        let result = self
+            .submit_with_sequence_number(payload.clone(), signer, fee_payer, sequence_number, true)
+            .await;
+        match result {
+            Err(VaultError::SequenceNumber(reason)) => {
+                warn!("Resyncing sequence number of {}: {}", sender, reason);
+                self.sequence_numbers.reset(&sender).await;
+                let sequence_number = self.allocate_sequence_number(sender, sponsored).await?;
+                let result = self
+                    .submit_with_sequence_number(payload, signer, fee_payer, sequence_number, true)
+                    .await;
+                if result.is_err() {
+                    self.sequence_numbers.reset(&sender).await;
+                }
+                result
+            }
+            Err(e) => {
+                // The allocated number was not used, resync before the next one
+                self.sequence_numbers.reset(&sender).await;
+                Err(e)
+            }
+            Ok(txn_hash) => Ok(txn_hash),
+        }
+    }
+
+    /// Submit several transactions from one account back to back, with
+    /// consecutive sequence numbers, without waiting for each to commit.
+    ///
+    /// Only the first transaction is simulated, as later ones depend on the
+    /// state left by earlier ones; they use the configured gas limit. Returns
+    /// the hashes of the submitted transactions, stopping at the first error.
+    pub async fn submit_pipelined(
+        &self,
+        payloads: Vec<TransactionPayload>,
+        signer: &dyn Signer,
+    ) -> Result<Vec<String>, VaultError> {
+        let sender = AuthenticationKey::ed25519(&signer.public_key()).account_address();
+        let mut hashes = Vec::with_capacity(payloads.len());
+        for (i, payload) in payloads.into_iter().enumerate() {
+            let sequence_number = self.next_sequence_number(sender).await?;
+            match self
+                .submit_with_sequence_number(payload, signer, None, sequence_number, i == 0)
+                .await
+            {
+                Ok(txn_hash) => hashes.push(txn_hash),
+                Err(e) => {
+                    self.sequence_numbers.reset(&sender).await;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(hashes)
+    }
+
+    async fn submit_with_sequence_number(
+        &self,
+        payload: TransactionPayload,
+        signer: &dyn Signer,
+        fee_payer: Option<&dyn Signer>,
+        sequence_number: u64,
+        simulate: bool,
+    ) -> Result<String, VaultError> {
+        let public_key = signer.public_key();
         let auth_key = AuthenticationKey::ed25519(&public_key);
         let sender = auth_key.account_address();
+        let fee_payer_key = fee_payer.map(|fee_payer| fee_payer.public_key());
+
+        let (max_gas_amount, gas_unit_price) = if simulate {
+            // Simulate first, so aborts fail fast instead of burning gas
+            let simulation = self
+                .simulate_as(
+                    payload.clone(),
+                    &public_key,
+                    fee_payer_key.as_ref(),
+                    Some(sequence_number),
+                )
+                .await?;
+            if !simulation.success {
+                return Err(VaultError::SimulationFailed(simulation.vm_status));
+            }
+            (
+                self.gas.max_gas_for(simulation.gas_used),
+                simulation.gas_unit_price,
+            )
+        } else {
+            (self.gas.max_gas_amount, self.gas_unit_price().await?)
+        };
 
-        println!("Public key: {}", public_key);
-        println!("Account address: {}", sender);
         // Generate raw transaction
         let raw_txn = self
-            .build_raw_txn(payload, sender, sequence_number_option)
+            .build_raw_txn_with_gas(
+                payload,
+                sender,
+                Some(sequence_number),
+                max_gas_amount,
+                gas_unit_price,
+            )
             .await?;
+        debug!(
+            "Submitting transaction {} from {} using up to {} gas units",
+            sequence_number, sender, max_gas_amount
+        );
 
-        // Sign transaction
-        let signature = self.sign_txn(&raw_txn, signer);
-        // Submit transaction
-        let resp = self
-            .client
-            .submit_transaction(SignedTransaction::new(
-                raw_txn,
-                TransactionAuthenticator::ed25519(public_key, signature),
-            ))
-            .await?;
+        // Sign transaction, by the sender and the fee payer if sponsored
+        let authenticator = match (fee_payer, fee_payer_key) {
+            (Some(fee_payer), Some(fee_payer_key)) => {
+                let fee_payer_addr = AuthenticationKey::ed25519(&fee_payer_key).account_address();
+                let signature = signer
+                    .sign_fee_payer_transaction(&raw_txn, fee_payer_addr)
+                    .await
+                    .map_err(VaultError::signing)?;
+                let fee_payer_signature = fee_payer
+                    .sign_fee_payer_transaction(&raw_txn, fee_payer_addr)
+                    .await
+                    .map_err(VaultError::signing)?;
+                authenticator(
+                    public_key,
+                    signature,
+                    Some((fee_payer_key, fee_payer_signature)),
+                )
+            }
+            _ => {
+                let signature = self.sign_txn(&raw_txn, signer).await?;
+                authenticator(public_key, signature, None)
+            }
+        };
+        self.submit_signed(SignedTransaction::new(raw_txn, authenticator))
+            .await
+    }
+
+    /// Submit an already signed transaction, e.g. one signed offline
+    pub async fn submit_signed(&self, signed_txn: SignedTransaction) -> Result<String, VaultError> {
+        let resp = self.client.submit_transaction(signed_txn).await?;
 
-        let txn_hash = resp.inner().get("hash").unwrap().as_str().unwrap();
-        println!("TXN_HASH: {:?}", txn_hash);
+        let txn_hash = resp
+            .inner()
+            .get("hash")
+            .and_then(|hash| hash.as_str())
+            .ok_or_else(|| VaultError::invalid_response("Missing transaction hash"))?;
+        info!("Submitted transaction {}", txn_hash);
 
         Ok(txn_hash.to_string())
     }
 
+    /// Read the parameters needed to build a transaction of `sender` offline.
+    ///
+    /// The transaction expires `expiration` after the current ledger time,
+    /// which bounds how long offline signing may take.
+    pub async fn transaction_params(
+        &self,
+        sender: &AccountAddress,
+        expiration: Duration,
+    ) -> Result<TransactionParams, VaultError> {
+        let state = self.client.get_state().await?;
+        Ok(TransactionParams {
+            sequence_number: self.get_sequence_number(sender).await?,
+            expiration_timestamp_secs: state.timestamp_usecs / 1_000_000 + expiration.as_secs(),
+            chain_id: state.chain_id,
+            max_gas_amount: self.gas.max_gas_amount,
+            gas_unit_price: self.gas_unit_price().await?,
+        })
+    }
+
+    /// Wait for a transaction to commit and return its outcome.
+    ///
+    /// An aborted transaction is still returned, with `success` unset and the
+    /// VM status; use [`TransactionOutcome::into_result`] to turn it into an
+    /// error. The node is polled with the configured [`PollBackoff`].
     pub async fn wait_for_transaction(
         &self,
         txn_hash: &str,
         timeout: Option<Duration>,
-    ) -> Result<(), RestError> {
+    ) -> Result<TransactionOutcome, VaultError> {
         let deadline = timeout.map(|t| Instant::now() + t);
+        let mut delay = self.poll.initial;
         loop {
-            if let Some(deadline) = deadline {
-                if Instant::now() >= deadline {
-                    return Err(RestError::Timeout("Transaction timeout"));
-                }
-            }
-
-            let status = match self
+            match self
                 .client
                 .get_transaction_by_hash(txn_hash.to_string())
                 .await
             {
-                Ok(resp) => match resp.inner().get("success") {
-                    Some(success) => {
-                        if success.as_bool().unwrap() {
-                            TransactionStatus::Confirmed
-                        } else {
-                            let vm_status =
-                                resp.inner().get("vm_status").unwrap().as_str().unwrap();
-                            TransactionStatus::Failed(vm_status.to_string())
-                        }
-                    }
-                    _ => TransactionStatus::Pending,
-                },
-                // Not found, let's wait
-                Err(_) => TransactionStatus::Pending,
-            };
-
-            println!("TX_STATUS: {:?}", status);
-            match status {
-                TransactionStatus::Confirmed => {
-                    return Ok(());
-                }
-                TransactionStatus::Failed(_status) => {
-                    return Err(RestError::Timeout("Transaction was failed"));
+                // Pending transactions have no `success` yet
+                Ok(resp) if resp.inner().get("success").is_some() => {
+                    let outcome = TransactionOutcome::from_response(resp.inner())?;
+                    debug!(
+                        "Transaction {} committed at {}: {}",
+                        txn_hash, outcome.version, outcome.vm_status
+                    );
+                    return Ok(outcome);
                 }
-                _ => {}
+                Ok(_) => debug!("Transaction {} is pending", txn_hash),
+                // Not found, let's wait
+                Err(e) => debug!("Transaction {} not found yet: {:?}", txn_hash, e),
             }
 
-            tokio::time::sleep(Duration::from_millis(500)).await;
+            let sleep = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(VaultError::Timeout(txn_hash.to_string()));
+                    }
+                    delay.min(remaining)
+                }
+                None => delay,
+            };
+            tokio::time::sleep(sleep).await;
+            delay = self.poll.next(delay);
         }
     }
-    pub async fn deposit_into_funding_with_transfer_to_cross_trading(
+
+    /// Decode the Ekiden events emitted by a committed transaction
+    pub fn decode_events(
         &self,
-        amount: u64,
-        owner_key: &KeyPair,
-        funding_key: &KeyPair,
-        trading_key: &KeyPair,
-    ) -> Result<String, RestError> {
-        println!("Depositing {} into vault", amount);
-        let funding_addr =
-            AuthenticationKey::ed25519(&funding_key.get_public_key()).account_address();
-        let trading_addr =
-            AuthenticationKey::ed25519(&trading_key.get_public_key()).account_address();
-
-        let auth_key = AuthenticationKey::ed25519(&owner_key.get_public_key());
-        let acc_addr = auth_key.account_address();
-        println!("Public key: {}", owner_key.get_public_key());
-        println!("Account address: {}", acc_addr);
-        println!("Funding address: {}", funding_addr);
-        println!("Trading address: {}", trading_addr);
-        println!("Vault contract address: {}", self.contract_addr);
-        let arguments = vec![
-            bcs::to_bytes(&funding_addr).unwrap(),
-            bcs::to_bytes(&trading_addr).unwrap(),
-            bcs::to_bytes(&self.asset_addr).unwrap(),
-            bcs::to_bytes(&amount).unwrap(), // Convert u128 to bytes
-        ];
-        let entry_function = EntryFunction::new(
-            ModuleId::new(self.contract_addr, "vault".to_string()),
-            "deposit_into_funding_with_transfer_to_cross_trading".to_string(),
-            vec![],
-            arguments,
-        );
+        outcome: &TransactionOutcome,
+    ) -> Result<Vec<EkidenEvent>, VaultError> {
+        EkidenEvent::decode_all(&self.contract_addr, &outcome.events)
+    }
+
+    /// Wait for a transaction to commit and return its receipt.
+    ///
+    /// Fails with [`VaultError::VmAbort`] if the transaction was committed but
+    /// aborted.
+    pub async fn wait_for_receipt(
+        &self,
+        txn_hash: &str,
+        timeout: Option<Duration>,
+    ) -> Result<TransactionReceipt, VaultError> {
+        let outcome = self
+            .wait_for_transaction(txn_hash, timeout)
+            .await?
+            .into_result()?;
+        Ok(outcome.receipt())
+    }
+
+    /// Encode the arguments of an Ekiden entry function and submit it
+    pub async fn submit_entry_function(
+        &self,
+        function: &EntryFunctionSpec,
+        args: Vec<MoveValue>,
+        signer: &dyn Signer,
+    ) -> Result<String, VaultError> {
+        let payload = function.payload(self.contract_addr, args)?;
+        self.submit(payload, signer, None).await
+    }
+
+    /// Submit an Ekiden entry function and wait for it to commit
+    pub async fn execute_entry_function(
+        &self,
+        function: &EntryFunctionSpec,
+        args: Vec<MoveValue>,
+        signer: &dyn Signer,
+    ) -> Result<TransactionReceipt, VaultError> {
+        let txn_hash = self.submit_entry_function(function, args, signer).await?;
+        self.confirm(txn_hash).await
+    }
 
-        let sequence_number = self
-            .get_sequence_number(&acc_addr)
+    /// Wait for a submitted vault transaction with the default timeout
+    async fn confirm(&self, txn_hash: String) -> Result<TransactionReceipt, VaultError> {
+        self.wait_for_receipt(&txn_hash, Some(DEFAULT_TRANSACTION_TIMEOUT))
             .await
-            .map_err(|_e| RestError::Timeout("Failed to get sequence number"))?;
-        println!(
-            "Start submitting transaction with sequence number: {}",
-            sequence_number
+    }
+
+    /// Deposit from the owner account into its funding account only
+    pub async fn deposit_into_funding(
+        &self,
+        amount: u64,
+        owner_key: &dyn Signer,
+        funding_addr: AccountAddress,
+    ) -> Result<TransactionReceipt, VaultError> {
+        info!("Depositing {} into funding {}", amount, funding_addr);
+        self.execute_entry_function(
+            &entry::vault::DEPOSIT_INTO_FUNDING,
+            vec![funding_addr.into(), self.asset_addr.into(), amount.into()],
+            owner_key,
+        )
+        .await
+    }
+
+    /// Move funds from the funding account to the linked trading account
+    pub async fn transfer_funding_to_trading(
+        &self,
+        amount: u64,
+        funding_key: &dyn Signer,
+    ) -> Result<TransactionReceipt, VaultError> {
+        info!("Transferring {} from funding to trading", amount);
+        self.execute_entry_function(
+            &entry::vault::TRANSFER_FUNDING_TO_TRADING,
+            vec![self.asset_addr.into(), amount.into()],
+            funding_key,
+        )
+        .await
+    }
+
+    /// Move funds from the trading account back to the linked funding account
+    pub async fn transfer_trading_to_funding(
+        &self,
+        amount: u64,
+        trading_key: &dyn Signer,
+    ) -> Result<TransactionReceipt, VaultError> {
+        info!("Transferring {} from trading to funding", amount);
+        self.execute_entry_function(
+            &entry::vault::TRANSFER_TRADING_TO_FUNDING,
+            vec![self.asset_addr.into(), amount.into()],
+            trading_key,
+        )
+        .await
+    }
+
+    /// Move cross margin of the trading account into the isolated margin of
+    /// a market
+    pub async fn transfer_cross_to_isolated(
+        &self,
+        market_addr: AccountAddress,
+        amount: u64,
+        trading_key: &dyn Signer,
+    ) -> Result<TransactionReceipt, VaultError> {
+        info!(
+            "Transferring {} from cross to isolated {}",
+            amount, market_addr
         );
-        self.submit(
-            TransactionPayload::EntryFunction(entry_function),
-            owner_key.get_private_key().clone(),
-            Some(sequence_number),
+        self.execute_entry_function(
+            &entry::vault::TRANSFER_CROSS_TO_ISOLATED,
+            vec![market_addr.into(), self.asset_addr.into(), amount.into()],
+            trading_key,
         )
         .await
     }
 
-    fn make_link_proof(root: &KeyPair, sub_account: &KeyPair) -> Vec<u8> {
-        let mut proof = vec![];
-        proof.extend(sub_account.get_public_key().to_bytes());
-        let auth_key = AuthenticationKey::ed25519(&root.get_public_key());
+    /// Withdraw from the vault to an arbitrary recipient address
+    pub async fn withdraw_to(
+        &self,
+        recipient: AccountAddress,
+        amount: u64,
+        signer: &dyn Signer,
+    ) -> Result<TransactionReceipt, VaultError> {
+        info!("Withdrawing {} from vault to {}", amount, recipient);
+        self.execute_entry_function(
+            &entry::vault::WITHDRAW_TO,
+            vec![recipient.into(), self.asset_addr.into(), amount.into()],
+            signer,
+        )
+        .await
+    }
+
+    /// Deposit into the funding account and on to cross trading, waiting
+    /// for the transaction to commit
+    pub async fn deposit_into_funding_with_transfer_to_cross_trading(
+        &self,
+        amount: u64,
+        owner_key: &dyn Signer,
+        funding_key: &dyn Signer,
+        trading_key: &dyn Signer,
+    ) -> Result<TransactionReceipt, VaultError> {
+        let payload = self.deposit_payload(amount, owner_key, funding_key, trading_key)?;
+        let txn_hash = self.submit(payload, owner_key, None).await?;
+        self.confirm(txn_hash).await
+    }
+
+    /// Deposit with the gas paid by `sponsor`, e.g. for a user's first
+    /// deposit before they hold any APT
+    pub async fn deposit_into_funding_with_transfer_to_cross_trading_sponsored(
+        &self,
+        amount: u64,
+        owner_key: &dyn Signer,
+        funding_key: &dyn Signer,
+        trading_key: &dyn Signer,
+        sponsor: &dyn Signer,
+    ) -> Result<TransactionReceipt, VaultError> {
+        let payload = self.deposit_payload(amount, owner_key, funding_key, trading_key)?;
+        let txn_hash = self
+            .submit_sponsored(payload, owner_key, sponsor, None)
+            .await?;
+        self.confirm(txn_hash).await
+    }
+
+    fn deposit_payload(
+        &self,
+        amount: u64,
+        owner_key: &dyn Signer,
+        funding_key: &dyn Signer,
+        trading_key: &dyn Signer,
+    ) -> Result<TransactionPayload, VaultError> {
+        let funding_addr = AuthenticationKey::ed25519(&funding_key.public_key()).account_address();
+        let trading_addr = AuthenticationKey::ed25519(&trading_key.public_key()).account_address();
+
+        let auth_key = AuthenticationKey::ed25519(&owner_key.public_key());
         let acc_addr = auth_key.account_address();
-        proof.extend(acc_addr.to_bytes());
-        assert_eq!(proof.len(), 64);
-        let sig = sub_account
-            .get_private_key()
-            .sign_arbitrary_message(acc_addr.to_bytes());
-        proof.extend(sig.to_bytes());
-        proof
+        info!(
+            "Depositing {} from {} into funding {} and trading {}",
+            amount, acc_addr, funding_addr, trading_addr
+        );
+        entry::vault::DEPOSIT_INTO_FUNDING_WITH_TRANSFER_TO_CROSS_TRADING.payload(
+            self.contract_addr,
+            vec![
+                funding_addr.into(),
+                trading_addr.into(),
+                self.asset_addr.into(),
+                amount.into(),
+            ],
+        )
+    }
+
+    /// Build a proof linking `sub_account` to the root account
+    pub async fn make_link_proof(
+        root: &dyn Signer,
+        sub_account: &dyn Signer,
+    ) -> Result<LinkProof, VaultError> {
+        let root_address = AuthenticationKey::ed25519(&root.public_key()).account_address();
+        LinkProof::build(root_address, sub_account).await
+    }
+
+    /// Check that a link proof is signed by its sub-account for `root_address`
+    pub fn verify_link_proof(proof: &LinkProof, root_address: &AccountAddress) -> bool {
+        proof.verify(root_address)
     }
 
+    /// Create an Ekiden user linking the funding and trading accounts to the
+    /// root account, waiting for the transaction to commit
     pub async fn create_ekiden_user(
         &self,
-        root: &KeyPair,
-        funding: &KeyPair,
-        trading: &KeyPair,
-    ) -> Result<String, RestError> {
-        let auth_key = AuthenticationKey::ed25519(&root.get_public_key());
+        root: &dyn Signer,
+        funding: &dyn Signer,
+        trading: &dyn Signer,
+    ) -> Result<TransactionReceipt, VaultError> {
+        let payload = self.create_user_payload(root, funding, trading).await?;
+        let txn_hash = self.submit(payload, root, None).await?;
+        self.confirm(txn_hash).await
+    }
+
+    /// Create an Ekiden user with the gas paid by `sponsor`, so the root
+    /// account needs no APT
+    pub async fn create_ekiden_user_sponsored(
+        &self,
+        root: &dyn Signer,
+        funding: &dyn Signer,
+        trading: &dyn Signer,
+        sponsor: &dyn Signer,
+    ) -> Result<TransactionReceipt, VaultError> {
+        let payload = self.create_user_payload(root, funding, trading).await?;
+        let txn_hash = self.submit_sponsored(payload, root, sponsor, None).await?;
+        self.confirm(txn_hash).await
+    }
+
+    async fn create_user_payload(
+        &self,
+        root: &dyn Signer,
+        funding: &dyn Signer,
+        trading: &dyn Signer,
+    ) -> Result<TransactionPayload, VaultError> {
+        let auth_key = AuthenticationKey::ed25519(&root.public_key());
         let acc_addr = auth_key.account_address();
-        let funding_proof = Self::make_link_proof(root, funding);
-        let trading_proof = Self::make_link_proof(root, trading);
-        let arguments = vec![
-            bcs::to_bytes(&funding_proof).unwrap(),
-            bcs::to_bytes(&trading_proof).unwrap(),
-            bcs::to_bytes(&self.asset_addr).unwrap(),
-        ];
-        let entry_function = EntryFunction::new(
-            ModuleId::new(self.contract_addr, "user".to_string()),
-            "create_ekiden_user".to_string(),
-            vec![],
-            arguments,
-        );
+        info!("Creating Ekiden user for {}", acc_addr);
+        let funding_proof = Self::make_link_proof(root, funding).await?;
+        let trading_proof = Self::make_link_proof(root, trading).await?;
+        entry::user::CREATE_EKIDEN_USER.payload(
+            self.contract_addr,
+            vec![
+                funding_proof.to_bytes().into(),
+                trading_proof.to_bytes().into(),
+                self.asset_addr.into(),
+            ],
+        )
+    }
 
-        let sequence_number = self
-            .get_sequence_number(&acc_addr)
+    /// Replace the funding or trading key of an existing Ekiden user.
+    ///
+    /// Signed by the root key; the previous sub-account key is unlinked, so a
+    /// compromised key can be revoked without creating a new user.
+    pub async fn rotate_sub_account_key(
+        &self,
+        root: &dyn Signer,
+        role: KeyRole,
+        new_key: &dyn Signer,
+    ) -> Result<TransactionReceipt, VaultError> {
+        let function = match role {
+            KeyRole::Funding => &entry::user::ROTATE_FUNDING_KEY,
+            KeyRole::Trading => &entry::user::ROTATE_TRADING_KEY,
+            KeyRole::Root => {
+                return Err(VaultError::InvalidArgument(
+                    "Root key cannot be rotated".to_string(),
+                ))
+            }
+        };
+        let proof = Self::make_link_proof(root, new_key).await?;
+        self.execute_entry_function(function, vec![proof.to_bytes().into()], root)
             .await
-            .map_err(|_e| RestError::Timeout("Failed to get sequence number"))?;
-        println!(
-            "Start submitting transaction with sequence number: {}",
-            sequence_number
-        );
-        self.submit(
-            TransactionPayload::EntryFunction(entry_function),
-            root.get_private_key().clone(),
-            Some(sequence_number),
-        )
-        .await
     }
+
+    /// Call a view function and return its values
+    pub async fn view(
+        &self,
+        function: String,
+        type_arguments: Vec<String>,
+        arguments: Vec<serde_json::Value>,
+    ) -> Result<Vec<serde_json::Value>, VaultError> {
+        let request = ViewRequest {
+            function,
+            type_arguments,
+            arguments,
+        };
+        Ok(self.client.view_function(request).await?.into_inner())
+    }
+
+    /// Get the funding and trading accounts currently linked to a root
+    /// address, with the `user::get_linked_accounts(address): (address, address)`
+    /// view
+    pub async fn get_linked_accounts(
+        &self,
+        root_address: &AccountAddress,
+    ) -> Result<LinkedAccounts, VaultError> {
+        let values = self
+            .view(
+                format!("{}::user::get_linked_accounts", self.contract_addr),
+                vec![],
+                vec![root_address.to_string().into()],
+            )
+            .await?;
+        let address = |index: usize| {
+            values
+                .get(index)
+                .and_then(|v| v.as_str())
+                .and_then(|v| AccountAddress::from_str(v).ok())
+                .ok_or_else(|| VaultError::invalid_response("Invalid linked accounts response"))
+        };
+        Ok(LinkedAccounts {
+            funding: address(0)?,
+            trading: address(1)?,
+        })
+    }
+
+    /// Check if a root address is registered as an Ekiden user, with the
+    /// `user::is_ekiden_user(address): bool` view
+    pub async fn is_registered(&self, root_address: &AccountAddress) -> Result<bool, VaultError> {
+        let values = self
+            .view(
+                format!("{}::user::is_ekiden_user", self.contract_addr),
+                vec![],
+                vec![root_address.to_string().into()],
+            )
+            .await?;
+        values
+            .first()
+            .and_then(|v| v.as_bool())
+            .ok_or_else(|| VaultError::invalid_response("Invalid registration response"))
+    }
+
+    /// Get the linked funding and trading accounts, or `None` if the root
+    /// address is not registered
+    pub async fn find_linked_accounts(
+        &self,
+        root_address: &AccountAddress,
+    ) -> Result<Option<LinkedAccounts>, VaultError> {
+        if !self.is_registered(root_address).await? {
+            return Ok(None);
+        }
+        self.get_linked_accounts(root_address).await.map(Some)
+    }
+
+    /// Check if `sub_account` is the key currently linked for `role`
+    pub async fn is_linked(
+        &self,
+        root_address: &AccountAddress,
+        role: KeyRole,
+        sub_account: &Ed25519PublicKey,
+    ) -> Result<bool, VaultError> {
+        let address = AuthenticationKey::ed25519(sub_account).account_address();
+        if role == KeyRole::Root {
+            return Ok(*root_address == address);
+        }
+        // An unregistered root has no linked keys
+        Ok(match self.find_linked_accounts(root_address).await? {
+            Some(linked) if role == KeyRole::Funding => linked.funding == address,
+            Some(linked) => linked.trading == address,
+            None => false,
+        })
+    }
+
+    /// Get the vault balance of an account for an asset.
+    ///
+    /// Calls the `vault::get_balance(address, address): u64` view, checked
+    /// against the published ABI by the localnet tests.
+    pub async fn get_vault_balance(
+        &self,
+        account: &AccountAddress,
+        asset_addr: &AccountAddress,
+    ) -> Result<u64, VaultError> {
+        let values = self
+            .view(
+                format!("{}::vault::get_balance", self.contract_addr),
+                vec![],
+                vec![account.to_string().into(), asset_addr.to_string().into()],
+            )
+            .await?;
+        parse_u64_value(values.first())
+    }
+
+    /// Get the fungible asset balance of an account's primary store, outside
+    /// the vault
+    pub async fn get_fungible_asset_balance(
+        &self,
+        owner: &AccountAddress,
+        asset_addr: &AccountAddress,
+    ) -> Result<u64, VaultError> {
+        let values = self
+            .view(
+                "0x1::primary_fungible_store::balance".to_string(),
+                vec!["0x1::fungible_asset::Metadata".to_string()],
+                vec![owner.to_string().into(), asset_addr.to_string().into()],
+            )
+            .await?;
+        parse_u64_value(values.first())
+    }
+
+    /// Withdraw from the vault to the signer, waiting for the transaction to
+    /// commit
     pub async fn withdraw_from_user(
         &self,
         amount: u64,
-        private_key: &str,
-    ) -> Result<String, RestError> {
-        println!("Withdrawing {} from vault", amount);
-        let signer = ed25519::Ed25519PrivateKey::from_encoded_string(private_key).unwrap();
-        let public_key = ed25519::PublicKey::from(&signer);
+        signer: &dyn Signer,
+    ) -> Result<TransactionReceipt, VaultError> {
+        let public_key = signer.public_key();
         let auth_key = AuthenticationKey::ed25519(&public_key);
         let acc_addr = auth_key.account_address();
-        println!("Public key: {}", public_key);
-        println!("Account address: {}", acc_addr);
-        println!("Vault contract address: {}", self.contract_addr);
-        let arguments = vec![
-            bcs::to_bytes(&self.asset_addr).unwrap(),
-            bcs::to_bytes(&amount).unwrap(), // Convert u128 to bytes
-        ];
-        let entry_function = EntryFunction::new(
-            ModuleId::new(self.contract_addr, "vault".to_string()),
-            "withdraw_from_user".to_string(),
+        info!("Withdrawing {} from vault to {}", amount, acc_addr);
+        self.execute_entry_function(
+            &entry::vault::WITHDRAW_FROM_USER,
+            vec![self.asset_addr.into(), amount.into()],
+            signer,
+        )
+        .await
+    }
+
+    /// Entry function depositing `amount` into the given funding and trading
+    /// accounts, e.g. to propose from a multisig account
+    pub fn deposit_entry_function(
+        &self,
+        amount: u64,
+        funding_addr: AccountAddress,
+        trading_addr: AccountAddress,
+    ) -> Result<EntryFunction, VaultError> {
+        entry::vault::DEPOSIT_INTO_FUNDING_WITH_TRANSFER_TO_CROSS_TRADING.build(
+            self.contract_addr,
             vec![],
-            arguments,
-        );
+            vec![
+                funding_addr.into(),
+                trading_addr.into(),
+                self.asset_addr.into(),
+                amount.into(),
+            ],
+        )
+    }
+
+    /// Entry function withdrawing `amount` from the vault
+    pub fn withdraw_entry_function(&self, amount: u64) -> Result<EntryFunction, VaultError> {
+        entry::vault::WITHDRAW_FROM_USER.build(
+            self.contract_addr,
+            vec![],
+            vec![self.asset_addr.into(), amount.into()],
+        )
+    }
 
-        let sequence_number = self
-            .get_sequence_number(&acc_addr)
+    /// Simulate, sign and submit a transaction from a multi-ed25519 account,
+    /// signed by at least `threshold` of its keys
+    pub async fn submit_multi_ed25519(
+        &self,
+        payload: TransactionPayload,
+        account: &MultiEd25519Account,
+        signers: &[&dyn Signer],
+        sequence_number_option: Option<u64>,
+    ) -> Result<String, VaultError> {
+        let sender = account.address();
+        let sequence_number = match sequence_number_option {
+            Some(sequence_number) => sequence_number,
+            None => self.next_sequence_number(sender).await?,
+        };
+        let result = self
+            .submit_multi_ed25519_with_sequence_number(payload, account, signers, sequence_number)
+            .await;
+        if result.is_err() && sequence_number_option.is_none() {
+            self.sequence_numbers.reset(&sender).await;
+        }
+        result
+    }
+
+    async fn submit_multi_ed25519_with_sequence_number(
+        &self,
+        payload: TransactionPayload,
+        account: &MultiEd25519Account,
+        signers: &[&dyn Signer],
+        sequence_number: u64,
+    ) -> Result<String, VaultError> {
+        let sender = account.address();
+
+        // Simulate first, so aborts fail fast instead of burning gas
+        let raw_txn = self
+            .build_raw_txn(payload.clone(), sender, Some(sequence_number))
+            .await?;
+        let resp = self
+            .client
+            .simulate_transaction(SignedTransaction::new(
+                raw_txn,
+                account.simulation_authenticator(signers)?,
+            ))
+            .await?;
+        let simulation = SimulationResult::from_response(resp.inner())?;
+        if !simulation.success {
+            return Err(VaultError::SimulationFailed(simulation.vm_status));
+        }
+
+        let raw_txn = self
+            .build_raw_txn_with_gas(
+                payload,
+                sender,
+                Some(sequence_number),
+                self.gas.max_gas_for(simulation.gas_used),
+                simulation.gas_unit_price,
+            )
+            .await?;
+        debug!(
+            "Submitting transaction {} from multi-ed25519 account {} with {} signatures",
+            sequence_number,
+            sender,
+            signers.len()
+        );
+        let authenticator = account.sign(&raw_txn, signers).await?;
+        self.submit_signed(SignedTransaction::new(raw_txn, authenticator))
             .await
-            .map_err(|_e| RestError::Timeout("Failed to get sequence number"))?;
-        println!(
-            "Start submitting transaction with sequence number: {}",
-            sequence_number
+    }
+
+    /// Deposit from a multi-ed25519 root account
+    pub async fn deposit_multi_ed25519(
+        &self,
+        amount: u64,
+        account: &MultiEd25519Account,
+        signers: &[&dyn Signer],
+        funding_addr: AccountAddress,
+        trading_addr: AccountAddress,
+    ) -> Result<TransactionReceipt, VaultError> {
+        info!(
+            "Depositing {} from multi-ed25519 account {} into funding {} and trading {}",
+            amount,
+            account.address(),
+            funding_addr,
+            trading_addr
+        );
+        let function = self.deposit_entry_function(amount, funding_addr, trading_addr)?;
+        let txn_hash = self
+            .submit_multi_ed25519(
+                TransactionPayload::EntryFunction(function),
+                account,
+                signers,
+                None,
+            )
+            .await?;
+        self.confirm(txn_hash).await
+    }
+
+    /// Withdraw from the vault to a multi-ed25519 account
+    pub async fn withdraw_from_user_multi_ed25519(
+        &self,
+        amount: u64,
+        account: &MultiEd25519Account,
+        signers: &[&dyn Signer],
+    ) -> Result<TransactionReceipt, VaultError> {
+        info!(
+            "Withdrawing {} from vault to multi-ed25519 account {}",
+            amount,
+            account.address()
+        );
+        let function = self.withdraw_entry_function(amount)?;
+        let txn_hash = self
+            .submit_multi_ed25519(
+                TransactionPayload::EntryFunction(function),
+                account,
+                signers,
+                None,
+            )
+            .await?;
+        self.confirm(txn_hash).await
+    }
+
+    /// Propose `function` as a transaction of an Aptos multisig account.
+    ///
+    /// Signed by one of the multisig owners, which also approves it.
+    pub async fn propose_multisig_transaction(
+        &self,
+        multisig_addr: AccountAddress,
+        function: EntryFunction,
+        owner: &dyn Signer,
+    ) -> Result<String, VaultError> {
+        info!(
+            "Proposing transaction on multisig account {}",
+            multisig_addr
         );
         self.submit(
-            TransactionPayload::EntryFunction(entry_function),
-            signer,
-            Some(sequence_number),
+            multisig_proposal_payload(multisig_addr, function)?,
+            owner,
+            None,
         )
         .await
     }
+
+    /// Approve the pending transaction `sequence_number` of a multisig account
+    pub async fn approve_multisig_transaction(
+        &self,
+        multisig_addr: AccountAddress,
+        sequence_number: u64,
+        owner: &dyn Signer,
+    ) -> Result<String, VaultError> {
+        self.submit(
+            multisig_approval_payload(multisig_addr, sequence_number)?,
+            owner,
+            None,
+        )
+        .await
+    }
+
+    /// Execute the next approved transaction of a multisig account, which
+    /// must be `function`
+    pub async fn execute_multisig_transaction(
+        &self,
+        multisig_addr: AccountAddress,
+        function: EntryFunction,
+        owner: &dyn Signer,
+    ) -> Result<String, VaultError> {
+        self.submit(
+            multisig_execution_payload(multisig_addr, function),
+            owner,
+            None,
+        )
+        .await
+    }
+
+    /// Get the sequence number the next multisig proposal will get
+    pub async fn next_multisig_sequence_number(
+        &self,
+        multisig_addr: &AccountAddress,
+    ) -> Result<u64, VaultError> {
+        let values = self
+            .view(
+                "0x1::multisig_account::next_sequence_number".to_string(),
+                vec![],
+                vec![multisig_addr.to_string().into()],
+            )
+            .await?;
+        parse_u64_value(values.first())
+    }
+}
+
+/// Parse a Move `u64`, which view functions return as a JSON string
+fn parse_u64_value(value: Option<&serde_json::Value>) -> Result<u64, VaultError> {
+    match value {
+        Some(serde_json::Value::String(v)) => v.parse::<u64>().ok(),
+        Some(serde_json::Value::Number(v)) => v.as_u64(),
+        _ => None,
+    }
+    .ok_or_else(|| VaultError::invalid_response(format!("Invalid u64 value: {:?}", value)))
+}
+
+/// Authenticator of a single-signer or fee-payer transaction
+fn authenticator(
+    public_key: Ed25519PublicKey,
+    signature: ed25519::Signature,
+    fee_payer: Option<(Ed25519PublicKey, ed25519::Signature)>,
+) -> TransactionAuthenticator {
+    match fee_payer {
+        Some((fee_payer_key, fee_payer_signature)) => TransactionAuthenticator::fee_payer(
+            AccountAuthenticator::ed25519(public_key, signature),
+            vec![],
+            vec![],
+            AuthenticationKey::ed25519(&fee_payer_key).account_address(),
+            AccountAuthenticator::ed25519(fee_payer_key, fee_payer_signature),
+        ),
+        None => TransactionAuthenticator::ed25519(public_key, signature),
+    }
+}
+
+fn custom_network(node_url: &str) -> Result<AptosNetwork, VaultError> {
+    let rest_url = Url::parse(node_url)
+        .map_err(|e| VaultError::InvalidNetwork(format!("{}: {}", node_url, e)))?;
+    Ok(AptosNetwork::new("custom", rest_url, None))
+}
+
+pub(crate) fn chain_id_from_u8(chain_id: u8) -> ChainId {
+    match chain_id {
+        1 => ChainId::Mainnet,
+        2 => ChainId::Testnet,
+        id => ChainId::Other(id),
+    }
+}
+
+/// Parse an account address, e.g. `0x1` or a full 32-byte hex address
+pub fn parse_address(address: &str) -> Result<AccountAddress, VaultError> {
+    AccountAddress::from_str(address).map_err(|e| VaultError::invalid_address(address, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_rejects_bad_input() {
+        let usdc = "0x9967e130f7419f791c240acc17dde966ec84ad41652e2e87083ee613f460d019";
+        assert!(matches!(
+            VaultContract::new("0x1", usdc, "moonnet"),
+            Err(VaultError::InvalidNetwork(_))
+        ));
+        assert!(matches!(
+            VaultContract::new("not-an-address", usdc, "testnet"),
+            Err(VaultError::InvalidAddress { .. })
+        ));
+        assert!(parse_address(usdc).is_ok());
+        assert!(matches!(
+            VaultContract::with_node_url("0x1", usdc, "not a url", None),
+            Err(VaultError::InvalidNetwork(_))
+        ));
+    }
+
+    #[test]
+    fn test_transaction_receipt_from_response() {
+        let response = serde_json::json!({
+            "hash": "0xabc",
+            "version": "123456",
+            "gas_used": "17",
+            "success": true,
+            "vm_status": "Executed successfully"
+        });
+        let receipt = TransactionReceipt::from_response(&response).unwrap();
+        assert_eq!(receipt.hash, "0xabc");
+        assert_eq!(receipt.version, 123456);
+        assert_eq!(receipt.gas_used, 17);
+        assert!(receipt.success);
+
+        assert!(TransactionReceipt::from_response(&serde_json::json!({"hash": "0xabc"})).is_err());
+    }
+
+    #[test]
+    fn test_parse_u64_value() {
+        assert_eq!(
+            parse_u64_value(Some(&serde_json::json!("1000"))).unwrap(),
+            1000
+        );
+        assert_eq!(parse_u64_value(Some(&serde_json::json!(7))).unwrap(), 7);
+        assert!(parse_u64_value(Some(&serde_json::json!("-1"))).is_err());
+        assert!(parse_u64_value(None).is_err());
+    }
+
+    #[test]
+    fn test_transaction_outcome_from_response() {
+        let response = serde_json::json!({
+            "hash": "0xabc",
+            "version": "42",
+            "gas_used": "9",
+            "success": false,
+            "vm_status": "Move abort in 0x1::vault: 0x10001",
+            "timestamp": "1700000000000000",
+            "events": [{
+                "guid": {"creation_number": "0", "account_address": "0x0"},
+                "sequence_number": "3",
+                "type": "0x1::transaction_fee::FeeStatement",
+                "data": {"total_charge_gas_units": "9"}
+            }]
+        });
+        let outcome = TransactionOutcome::from_response(&response).unwrap();
+        assert_eq!(outcome.version, 42);
+        assert_eq!(outcome.timestamp_usecs, 1_700_000_000_000_000);
+        assert_eq!(outcome.events.len(), 1);
+        assert_eq!(
+            outcome.events[0].event_type,
+            "0x1::transaction_fee::FeeStatement"
+        );
+        assert_eq!(outcome.events[0].sequence_number, 3);
+        assert_eq!(outcome.receipt().gas_used, 9);
+        assert!(matches!(
+            outcome.into_result(),
+            Err(VaultError::VmAbort { vm_status, .. }) if vm_status.contains("0x10001")
+        ));
+    }
+
+    #[test]
+    fn test_poll_backoff() {
+        let backoff = PollBackoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_millis(300),
+            multiplier: 2.0,
+        };
+        assert_eq!(backoff.next(backoff.initial), Duration::from_millis(200));
+        assert_eq!(
+            backoff.next(Duration::from_millis(200)),
+            Duration::from_millis(300)
+        );
+    }
+
+    #[test]
+    fn test_max_gas_for() {
+        let gas = GasConfig::default();
+        assert_eq!(gas.max_gas_for(1000), 1500);
+        assert_eq!(gas.max_gas_for(0), 1);
+        assert_eq!(gas.max_gas_for(1_000_000), gas.max_gas_amount);
+    }
+
+    #[test]
+    fn test_simulation_result_from_response() {
+        let response = serde_json::json!([{
+            "success": false,
+            "vm_status": "Move abort in 0x1::coin: EINSUFFICIENT_BALANCE(0x10006)",
+            "gas_used": "12",
+            "gas_unit_price": "100",
+        }]);
+        let result = SimulationResult::from_response(&response).unwrap();
+        assert!(!result.success);
+        assert!(result.vm_status.contains("EINSUFFICIENT_BALANCE"));
+        assert_eq!(result.gas_used, 12);
+        assert_eq!(result.gas_unit_price, 100);
+
+        assert!(SimulationResult::from_response(&serde_json::json!([])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_known_chain_id_is_not_fetched() {
+        let usdc = "0x9967e130f7419f791c240acc17dde966ec84ad41652e2e87083ee613f460d019";
+        // Nothing listens on this port, so the chain id must come from the constructor
+        let vault =
+            VaultContract::with_node_url("0x1", usdc, "http://127.0.0.1:9/v1", Some(4)).unwrap();
+        assert!(matches!(vault.get_chain_id().await, Ok(ChainId::Other(4))));
+        assert!(matches!(chain_id_from_u8(1), ChainId::Mainnet));
+    }
 }
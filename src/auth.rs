@@ -1,54 +1,71 @@
 use crate::error::{EkidenError, Result};
+use crate::secret::{SecretString, REDACTED};
+use crate::signer::{signature_hex, Signer};
 use crate::types::{AuthorizeParams, AuthorizeResponse};
 use crate::utils::{format, KeyPair};
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
+use std::fmt::{Debug, Formatter};
 use std::iter;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Authentication manager for the Ekiden client
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Auth {
-    key_pair: Option<KeyPair>,
-    token: Option<String>,
+    signer: Option<Arc<dyn Signer>>,
+    token: Option<SecretString>,
+}
+
+impl Debug for Auth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Auth")
+            .field("public_key", &self.public_key())
+            .field("token", &self.token.as_ref().map(|_| REDACTED))
+            .finish()
+    }
 }
 
 impl Auth {
     /// Create a new authentication manager
     pub fn new() -> Self {
         Self {
-            key_pair: None,
+            signer: None,
             token: None,
         }
     }
 
     /// Set the key pair for signing operations
-    pub fn with_key_pair(mut self, key_pair: KeyPair) -> Self {
-        self.key_pair = Some(key_pair);
+    pub fn with_key_pair(self, key_pair: KeyPair) -> Self {
+        self.with_signer(Arc::new(key_pair))
+    }
+
+    /// Set the signer for signing operations
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
         self
     }
 
     /// Set the key pair from a private key hex string
-    pub fn with_private_key(mut self, private_key: &str) -> Result<Self> {
+    pub fn with_private_key(self, private_key: &str) -> Result<Self> {
         let key_pair = KeyPair::from_private_key(private_key)?;
-        self.key_pair = Some(key_pair);
-        Ok(self)
+        Ok(self.with_key_pair(key_pair))
     }
 
     /// Set the authentication token
     pub fn with_token<S: Into<String>>(mut self, token: S) -> Self {
-        self.token = Some(token.into());
+        self.token = Some(SecretString::new(token));
         self
     }
 
     /// Get the current authentication token
     pub fn token(&self) -> Option<&str> {
-        self.token.as_deref()
+        self.token.as_ref().map(|token| token.expose_secret())
     }
 
     /// Set the authentication token
     pub fn set_token<S: Into<String>>(&mut self, token: S) {
-        self.token = Some(token.into());
+        self.token = Some(SecretString::new(token));
     }
 
     /// Clear the authentication token
@@ -63,29 +80,29 @@ impl Auth {
 
     /// Get the public key if available
     pub fn public_key(&self) -> Option<String> {
-        self.key_pair.as_ref().map(|kp| kp.public_key())
+        self.signer.as_ref().map(|signer| signer.public_key_hex())
     }
 
-    /// Generate authorization parameters for the /authorize endpoint
-    pub fn generate_authorize_params(&self) -> Result<AuthorizeParams> {
-        let mut rng = thread_rng();
-        let key_pair = self
-            .key_pair
-            .as_ref()
-            .ok_or_else(|| EkidenError::auth("No key pair available for signing"))?;
+    /// Get the signer if available
+    pub fn signer(&self) -> Option<Arc<dyn Signer>> {
+        self.signer.clone()
+    }
 
-        let public_key = key_pair.public_key();
+    /// Generate authorization parameters for the /authorize endpoint
+    pub async fn generate_authorize_params(&self) -> Result<AuthorizeParams> {
+        let signer = self.ensure_signer()?;
+        let public_key = signer.public_key_hex();
 
         // Validate the generated parameters
         format::validate_public_key(&public_key)?;
         let now = SystemTime::now();
         let timestamp = now.duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
         let nonce: String = iter::repeat(())
-            .map(|()| rng.sample(Alphanumeric))
+            .map(|()| thread_rng().sample(Alphanumeric))
             .take(10) // Generate a string of 10 characters
             .collect();
 
-        let signature = key_pair.sign_authorize(timestamp, &nonce);
+        let signature = signature_hex(&signer.sign_authorize(timestamp, &nonce).await?);
         format::validate_signature(&signature)?;
 
         Ok(AuthorizeParams {
@@ -96,44 +113,47 @@ impl Auth {
         })
     }
 
-    /// Sign a message with the current key pair
-    pub fn sign_message(&self, message: &[u8]) -> Result<String> {
-        let key_pair = self
-            .key_pair
-            .as_ref()
-            .ok_or_else(|| EkidenError::auth("No key pair available for signing"))?;
-
-        let signature = key_pair.sign(message);
-        format::normalize_signature(&signature)
+    /// Sign a message with the current signer
+    pub async fn sign_message(&self, message: &[u8]) -> Result<String> {
+        let signature = self.ensure_signer()?.sign_message(message).await?;
+        format::normalize_signature(&signature_hex(&signature))
     }
 
     /// Sign arbitrary data as JSON string
-    pub fn sign_json<T: serde::Serialize>(&self, data: &T) -> Result<String> {
+    pub async fn sign_json<T: serde::Serialize>(&self, data: &T) -> Result<String> {
         let json_str = serde_json::to_string(data)?;
-        self.sign_message(json_str.as_bytes())
+        self.sign_message(json_str.as_bytes()).await
     }
 
     /// Generate a bearer token header value
     pub fn bearer_token(&self) -> Option<String> {
-        self.token.as_ref().map(|token| format!("Bearer {}", token))
+        self.token
+            .as_ref()
+            .map(|token| format!("Bearer {}", token.expose_secret()))
+    }
+
+    /// Check if a signer is available
+    pub fn has_signer(&self) -> bool {
+        self.signer.is_some()
     }
 
     /// Check if a key pair is available
+    #[deprecated(note = "use `has_signer`, which also covers external signers")]
     pub fn has_key_pair(&self) -> bool {
-        self.key_pair.is_some()
+        self.has_signer()
     }
 
     /// Process an authorization response and store the token
     pub fn process_authorize_response(&mut self, response: AuthorizeResponse) {
-        self.token = Some(response.token);
+        self.token = Some(SecretString::new(response.token));
     }
 
     /// Create auth headers for HTTP requests
     pub fn auth_headers(&self) -> std::collections::HashMap<String, String> {
         let mut headers = std::collections::HashMap::new();
 
-        if let Some(token) = &self.token {
-            headers.insert("Authorization".to_string(), format!("Bearer {}", token));
+        if let Some(bearer) = self.bearer_token() {
+            headers.insert("Authorization".to_string(), bearer);
         }
 
         headers
@@ -149,11 +169,11 @@ impl Auth {
         Ok(())
     }
 
-    /// Ensure the client has a key pair for signing
-    pub fn ensure_key_pair(&self) -> Result<&KeyPair> {
-        self.key_pair
+    /// Ensure the client has a signer
+    pub fn ensure_signer(&self) -> Result<&Arc<dyn Signer>> {
+        self.signer
             .as_ref()
-            .ok_or_else(|| EkidenError::auth("No key pair available. Please set a private key."))
+            .ok_or_else(|| EkidenError::auth("No signer available. Please set a private key."))
     }
 }
 
@@ -192,6 +212,12 @@ impl AuthBuilder {
         self
     }
 
+    /// Set the signer
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.auth = self.auth.with_signer(signer);
+        self
+    }
+
     /// Build the auth instance
     pub fn build(self) -> Auth {
         self.auth
@@ -213,7 +239,7 @@ mod tests {
     fn test_auth_creation() {
         let auth = Auth::new();
         assert!(!auth.is_authenticated());
-        assert!(!auth.has_key_pair());
+        assert!(!auth.has_signer());
     }
 
     #[test]
@@ -221,7 +247,7 @@ mod tests {
         let key_pair = KeyPair::generate();
         let auth = Auth::new().with_key_pair(key_pair);
 
-        assert!(auth.has_key_pair());
+        assert!(auth.has_signer());
         assert!(auth.public_key().is_some());
     }
 
@@ -230,8 +256,10 @@ mod tests {
         let key_pair = KeyPair::generate();
         let private_key = key_pair.private_key();
 
-        let auth = Auth::new().with_private_key(&private_key).unwrap();
-        assert!(auth.has_key_pair());
+        let auth = Auth::new()
+            .with_private_key(private_key.expose_secret())
+            .unwrap();
+        assert!(auth.has_signer());
         assert_eq!(auth.public_key().unwrap(), key_pair.public_key());
     }
 
@@ -242,23 +270,23 @@ mod tests {
         assert_eq!(auth.token(), Some("test_token"));
     }
 
-    #[test]
-    fn test_generate_authorize_params() {
+    #[tokio::test]
+    async fn test_generate_authorize_params() {
         let key_pair = KeyPair::generate();
         let auth = Auth::new().with_key_pair(key_pair.clone());
 
-        let params = auth.generate_authorize_params().unwrap();
+        let params = auth.generate_authorize_params().await.unwrap();
         assert!(!params.signature.is_empty());
         assert_eq!(params.public_key, key_pair.public_key());
     }
 
-    #[test]
-    fn test_sign_message() {
+    #[tokio::test]
+    async fn test_sign_message() {
         let key_pair = KeyPair::generate();
         let auth = Auth::new().with_key_pair(key_pair);
 
         let message = b"test message";
-        let signature = auth.sign_message(message).unwrap();
+        let signature = auth.sign_message(message).await.unwrap();
         assert!(!signature.is_empty());
         assert!(signature.starts_with("0x"));
     }
@@ -269,13 +297,13 @@ mod tests {
         let private_key = key_pair.private_key();
 
         let auth = AuthBuilder::new()
-            .private_key(&private_key)
+            .private_key(private_key.expose_secret())
             .unwrap()
             .token("test_token")
             .build();
 
         assert!(auth.is_authenticated());
-        assert!(auth.has_key_pair());
+        assert!(auth.has_signer());
         assert_eq!(auth.token(), Some("test_token"));
     }
 
@@ -289,13 +317,26 @@ mod tests {
     }
 
     #[test]
-    fn test_ensure_key_pair() {
+    fn test_ensure_signer() {
         let auth = Auth::new();
-        assert!(auth.ensure_key_pair().is_err());
+        assert!(auth.ensure_signer().is_err());
 
         let key_pair = KeyPair::generate();
         let auth = Auth::new().with_key_pair(key_pair);
-        assert!(auth.ensure_key_pair().is_ok());
+        assert!(auth.ensure_signer().is_ok());
+    }
+
+    #[test]
+    fn test_auth_debug_redacted() {
+        let key_pair = KeyPair::generate();
+        let auth = Auth::new()
+            .with_key_pair(key_pair.clone())
+            .with_token("test_token");
+
+        let debug = format!("{:?}", auth);
+        assert!(!debug.contains("test_token"));
+        assert!(!debug.contains(&key_pair.private_key().expose_secret()[2..]));
+        assert!(debug.contains(&key_pair.public_key()));
     }
 
     #[test]
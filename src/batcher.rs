@@ -0,0 +1,386 @@
+use crate::client::EkidenClient;
+use crate::error::{EkidenError, Result};
+use crate::types::{
+    ActionPayload, IntentOutput, OrderCancel, OrderCancelAction, OrderCancelOutput, OrderCreate,
+    OrderCreateAction, OrderCreateOutput, SendIntentResponse,
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::debug;
+
+/// Configuration for the intent batcher
+#[derive(Debug, Clone)]
+pub struct BatcherConfig {
+    /// How long to collect orders after the first one arrives
+    pub window: Duration,
+    /// Maximum number of creates or cancels in one intent
+    pub max_batch_size: usize,
+}
+
+impl Default for BatcherConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(20),
+            max_batch_size: 50,
+        }
+    }
+}
+
+impl BatcherConfig {
+    /// Set the batching window
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set the maximum batch size
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
+}
+
+/// Result of a single order in a batch, resolved once its intent is committed
+pub struct PendingResult<T> {
+    receiver: oneshot::Receiver<Result<T>>,
+}
+
+impl<T> Future for PendingResult<T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver).poll(cx).map(|result| {
+            result.unwrap_or_else(|_| Err(EkidenError::general("Intent batcher stopped")))
+        })
+    }
+}
+
+enum BatchRequest {
+    Create(OrderCreate, oneshot::Sender<Result<OrderCreateOutput>>),
+    Cancel(OrderCancel, oneshot::Sender<Result<OrderCancelOutput>>),
+}
+
+#[derive(Default)]
+struct Batch {
+    creates: Vec<(OrderCreate, oneshot::Sender<Result<OrderCreateOutput>>)>,
+    cancels: Vec<(OrderCancel, oneshot::Sender<Result<OrderCancelOutput>>)>,
+}
+
+impl Batch {
+    fn push(&mut self, request: BatchRequest) {
+        match request {
+            BatchRequest::Create(order, tx) => self.creates.push((order, tx)),
+            BatchRequest::Cancel(cancel, tx) => self.cancels.push((cancel, tx)),
+        }
+    }
+
+    fn is_full(&self, max_batch_size: usize) -> bool {
+        self.creates.len() >= max_batch_size || self.cancels.len() >= max_batch_size
+    }
+}
+
+/// Coalesces orders and cancels into as few signed intents as possible.
+///
+/// Requests arriving within the batching window are submitted together, one
+/// signature per intent, and each request resolves with its own output.
+/// Cancels of a batch are submitted before its creates.
+pub struct IntentBatcher {
+    sender: mpsc::UnboundedSender<BatchRequest>,
+    handle: JoinHandle<()>,
+}
+
+impl IntentBatcher {
+    /// Create a new batcher submitting through the client's trading key
+    pub fn new(client: EkidenClient, config: BatcherConfig) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let handle = tokio::spawn(Self::run(client, config, receiver));
+        Self { sender, handle }
+    }
+
+    /// Queue an order, resolving with its sid once the batch is committed
+    pub fn create(&self, order: OrderCreate) -> PendingResult<OrderCreateOutput> {
+        let (tx, receiver) = oneshot::channel();
+        if let Err(mpsc::error::SendError(BatchRequest::Create(_, tx))) =
+            self.sender.send(BatchRequest::Create(order, tx))
+        {
+            let _ = tx.send(Err(EkidenError::general("Intent batcher stopped")));
+        }
+        PendingResult { receiver }
+    }
+
+    /// Queue a cancel, resolving once the batch is committed
+    pub fn cancel<S: Into<String>>(&self, sid: S) -> PendingResult<OrderCancelOutput> {
+        let (tx, receiver) = oneshot::channel();
+        let cancel = OrderCancel { sid: sid.into() };
+        if let Err(mpsc::error::SendError(BatchRequest::Cancel(_, tx))) =
+            self.sender.send(BatchRequest::Cancel(cancel, tx))
+        {
+            let _ = tx.send(Err(EkidenError::general("Intent batcher stopped")));
+        }
+        PendingResult { receiver }
+    }
+
+    /// Stop the batcher after the queued requests are submitted
+    pub async fn shutdown(self) {
+        drop(self.sender);
+        let _ = self.handle.await;
+    }
+
+    async fn run(
+        client: EkidenClient,
+        config: BatcherConfig,
+        mut receiver: mpsc::UnboundedReceiver<BatchRequest>,
+    ) {
+        let max_batch_size = config.max_batch_size.max(1);
+        while let Some(first) = receiver.recv().await {
+            let mut batch = Batch::default();
+            batch.push(first);
+
+            let deadline = Instant::now() + config.window;
+            while !batch.is_full(max_batch_size) {
+                match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                    Ok(Some(request)) => batch.push(request),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+
+            debug!(
+                "Submitting batch of {} creates and {} cancels",
+                batch.creates.len(),
+                batch.cancels.len()
+            );
+            Self::submit(&client, batch).await;
+        }
+    }
+
+    async fn submit(client: &EkidenClient, batch: Batch) {
+        if !batch.cancels.is_empty() {
+            let (cancels, waiters): (Vec<_>, Vec<_>) = batch.cancels.into_iter().unzip();
+            let sids: Vec<String> = cancels.iter().map(|c| c.sid.clone()).collect();
+            let result = client
+                .submit_action(ActionPayload::OrderCancel(OrderCancelAction { cancels }))
+                .await;
+            resolve_cancels(&sids, waiters, result);
+        }
+
+        if !batch.creates.is_empty() {
+            let (orders, waiters): (Vec<_>, Vec<_>) = batch.creates.into_iter().unzip();
+            let client_order_ids: Vec<Option<String>> =
+                orders.iter().map(|o| o.client_order_id.clone()).collect();
+            let result = client
+                .submit_action(ActionPayload::OrderCreate(OrderCreateAction { orders }))
+                .await;
+            resolve_creates(&client_order_ids, waiters, result);
+        }
+    }
+}
+
+/// Resolve the waiters of a create batch.
+///
+/// Outputs are matched by echoed client order id, and by position only when
+/// there is exactly one output per order.
+fn resolve_creates(
+    client_order_ids: &[Option<String>],
+    waiters: Vec<oneshot::Sender<Result<OrderCreateOutput>>>,
+    result: Result<SendIntentResponse>,
+) {
+    let outputs = match result.and_then(|response| match response.output {
+        IntentOutput::OrderCreate(output) => Ok(output.outputs),
+        other => Err(EkidenError::general(format!(
+            "Unexpected output for order create intent: {:?}",
+            other
+        ))),
+    }) {
+        Ok(outputs) => outputs,
+        Err(e) => {
+            for waiter in waiters {
+                let _ = waiter.send(Err(shared_error(&e)));
+            }
+            return;
+        }
+    };
+
+    let positional = outputs.len() == waiters.len();
+    for (index, (client_order_id, waiter)) in client_order_ids.iter().zip(waiters).enumerate() {
+        let by_id = client_order_id.as_ref().and_then(|id| {
+            outputs
+                .iter()
+                .find(|o| o.client_order_id.as_ref() == Some(id))
+        });
+        let output = match by_id {
+            Some(output) => Some(output.clone()),
+            None if positional => outputs.get(index).cloned(),
+            None => None,
+        };
+        let _ = waiter.send(output.ok_or_else(|| {
+            EkidenError::general(format!(
+                "Cannot match order to the {} outputs of a batch of {}",
+                outputs.len(),
+                client_order_ids.len()
+            ))
+        }));
+    }
+}
+
+/// Resolve the waiters of a cancel batch; outputs are matched by sid
+fn resolve_cancels(
+    sids: &[String],
+    waiters: Vec<oneshot::Sender<Result<OrderCancelOutput>>>,
+    result: Result<SendIntentResponse>,
+) {
+    let outputs = match result.and_then(|response| match response.output {
+        IntentOutput::OrderCancel(output) => Ok(output.outputs),
+        other => Err(EkidenError::general(format!(
+            "Unexpected output for order cancel intent: {:?}",
+            other
+        ))),
+    }) {
+        Ok(outputs) => outputs,
+        Err(e) => {
+            for waiter in waiters {
+                let _ = waiter.send(Err(shared_error(&e)));
+            }
+            return;
+        }
+    };
+
+    for (sid, waiter) in sids.iter().zip(waiters) {
+        let _ = waiter.send(
+            outputs
+                .iter()
+                .find(|o| &o.sid == sid)
+                .cloned()
+                .ok_or_else(|| EkidenError::general(format!("Order {} was not cancelled", sid))),
+        );
+    }
+}
+
+/// Copy a batch error for each of the batch's waiters, keeping its kind so
+/// that [`EkidenError::is_retryable`] gives the same answer
+fn shared_error(e: &EkidenError) -> EkidenError {
+    match e {
+        EkidenError::Api { status, message } => EkidenError::api(*status, message.clone()),
+        EkidenError::Auth(message) => EkidenError::auth(message.clone()),
+        EkidenError::Validation(message) => EkidenError::validation(message.clone()),
+        EkidenError::Config(message) => EkidenError::config(message.clone()),
+        EkidenError::Crypto(message) => EkidenError::crypto(message.clone()),
+        EkidenError::Network(message) => EkidenError::network(message.clone()),
+        // reqwest errors can't be cloned; a transient one stays retryable
+        EkidenError::Http(_) if e.is_retryable() => EkidenError::network(e.to_string()),
+        EkidenError::Timeout => EkidenError::Timeout,
+        EkidenError::ConnectionClosed => EkidenError::ConnectionClosed,
+        EkidenError::RateLimit => EkidenError::RateLimit,
+        other => EkidenError::general(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{OrderCancelIntentOutput, OrderCreateIntentOutput};
+
+    fn response(output: IntentOutput) -> SendIntentResponse {
+        SendIntentResponse {
+            output,
+            seq: 1,
+            version: 1,
+            timestamp: 1,
+        }
+    }
+
+    fn created(sid: &str, client_order_id: Option<&str>) -> OrderCreateOutput {
+        OrderCreateOutput {
+            sid: sid.to_string(),
+            client_order_id: client_order_id.map(|s| s.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_creates() {
+        let (tx1, rx1) = oneshot::channel();
+        let (tx2, rx2) = oneshot::channel();
+        let output = IntentOutput::OrderCreate(OrderCreateIntentOutput {
+            outputs: vec![created("1", None), created("2", None)],
+        });
+
+        resolve_creates(&[None, None], vec![tx1, tx2], Ok(response(output)));
+
+        assert_eq!(PendingResult { receiver: rx1 }.await.unwrap().sid, "1");
+        assert_eq!(PendingResult { receiver: rx2 }.await.unwrap().sid, "2");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_creates_short_output() {
+        // Without echoed ids a short output cannot be matched: every waiter fails
+        let (tx1, rx1) = oneshot::channel();
+        let (tx2, rx2) = oneshot::channel();
+        let output = IntentOutput::OrderCreate(OrderCreateIntentOutput {
+            outputs: vec![created("1", None)],
+        });
+        resolve_creates(&[None, None], vec![tx1, tx2], Ok(response(output)));
+        assert!(PendingResult { receiver: rx1 }.await.is_err());
+        assert!(PendingResult { receiver: rx2 }.await.is_err());
+
+        // With echoed ids, the matched order resolves whatever its position
+        let (tx1, rx1) = oneshot::channel();
+        let (tx2, rx2) = oneshot::channel();
+        let output = IntentOutput::OrderCreate(OrderCreateIntentOutput {
+            outputs: vec![created("2", Some("b"))],
+        });
+        let ids = [Some("a".to_string()), Some("b".to_string())];
+        resolve_creates(&ids, vec![tx1, tx2], Ok(response(output)));
+        assert!(PendingResult { receiver: rx1 }.await.is_err());
+        assert_eq!(PendingResult { receiver: rx2 }.await.unwrap().sid, "2");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_cancels() {
+        let (tx1, rx1) = oneshot::channel();
+        let (tx2, rx2) = oneshot::channel();
+        let output = IntentOutput::OrderCancel(OrderCancelIntentOutput {
+            outputs: vec![OrderCancelOutput {
+                sid: "b".to_string(),
+            }],
+        });
+        let sids = vec!["a".to_string(), "b".to_string()];
+
+        resolve_cancels(&sids, vec![tx1, tx2], Ok(response(output)));
+
+        assert!(PendingResult { receiver: rx1 }.await.is_err());
+        assert_eq!(PendingResult { receiver: rx2 }.await.unwrap().sid, "b");
+    }
+
+    #[tokio::test]
+    async fn test_batch_error_is_shared() {
+        let (tx1, rx1) = oneshot::channel();
+        let (tx2, rx2) = oneshot::channel();
+
+        resolve_creates(
+            &[None, None],
+            vec![tx1, tx2],
+            Err(EkidenError::api(400, "rejected".to_string())),
+        );
+
+        for rx in [rx1, rx2] {
+            let err = PendingResult { receiver: rx }.await.unwrap_err();
+            assert!(matches!(err, EkidenError::Api { status: 400, .. }));
+        }
+
+        // An unknown outcome stays retryable for every waiter
+        for e in [
+            EkidenError::network("connection reset"),
+            EkidenError::ConnectionClosed,
+            EkidenError::api(503, "unavailable".to_string()),
+        ] {
+            let (tx, rx) = oneshot::channel();
+            resolve_cancels(&["1".to_string()], vec![tx], Err(e));
+            let err = PendingResult { receiver: rx }.await.unwrap_err();
+            assert!(err.is_retryable(), "{}", err);
+        }
+    }
+}
@@ -1,15 +1,23 @@
 use crate::auth::Auth;
 use crate::config::EkidenConfig;
 use crate::error::{EkidenError, Result};
+use crate::keystore::{KeyRole, Keystore};
+use crate::nonce::NonceManager;
+use crate::orders::{ClientOrderTracker, IntentSubmission, ReplaceResult};
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::secret::SecretString;
+use crate::session::{Session, SessionStore, SessionToken, SESSION_MIN_TTL};
+use crate::signer::{signature_hex, Signer};
 use crate::types::*;
 use crate::ws::WebSocketClient;
-use aptos_crypto::{ed25519::Ed25519PrivateKey, ed25519::Signature, ValidCryptoMaterialStringExt};
+use aptos_crypto::ed25519::Signature;
 use reqwest::{Client, Response};
 use serde::de::DeserializeOwned;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 /// Main Ekiden client for interacting with the API and WebSocket
 #[derive(Debug, Clone)]
 pub struct EkidenClient {
@@ -19,6 +27,9 @@ pub struct EkidenClient {
     funding_auth: Arc<RwLock<Auth>>,
     trading_auth: Arc<RwLock<Auth>>,
     ws_client: Option<Arc<RwLock<WebSocketClient>>>,
+    client_orders: ClientOrderTracker,
+    nonces: Arc<NonceManager>,
+    rate_limiter: Arc<std::sync::RwLock<Option<Arc<RateLimiter>>>>,
 }
 
 impl EkidenClient {
@@ -33,14 +44,38 @@ impl EkidenClient {
             config.websocket_url().clone(),
         ))));
 
-        Ok(Self {
+        Ok(Self::from_transport(config, http_client, ws_client))
+    }
+
+    /// Create a client for another account that shares this client's HTTP
+    /// connection pool and WebSocket connection.
+    ///
+    /// Credentials, tokens, nonces, tracked orders and the rate limit are not
+    /// shared.
+    pub fn with_shared_transport(&self) -> Self {
+        Self::from_transport(
+            self.config.clone(),
+            self.http_client.clone(),
+            self.ws_client.clone(),
+        )
+    }
+
+    fn from_transport(
+        config: EkidenConfig,
+        http_client: Client,
+        ws_client: Option<Arc<RwLock<WebSocketClient>>>,
+    ) -> Self {
+        Self {
             config,
             http_client,
             auth: Arc::new(RwLock::new(Auth::new())),
             funding_auth: Arc::new(RwLock::new(Auth::new())),
             trading_auth: Arc::new(RwLock::new(Auth::new())),
             ws_client,
-        })
+            client_orders: ClientOrderTracker::new(),
+            nonces: Arc::new(NonceManager::new()),
+            rate_limiter: Arc::new(std::sync::RwLock::new(None)),
+        }
     }
 
     /// Create a client with default configuration
@@ -82,6 +117,29 @@ impl EkidenClient {
         Ok(())
     }
 
+    /// Set the signer for signing operations
+    pub async fn set_signer(&self, signer: Arc<dyn Signer>) {
+        let mut auth = self.auth.write().await;
+        *auth = auth.clone().with_signer(signer);
+    }
+
+    /// Set the funding account signer
+    pub async fn set_funding_signer(&self, signer: Arc<dyn Signer>) {
+        let mut auth = self.funding_auth.write().await;
+        *auth = auth.clone().with_signer(signer);
+    }
+
+    /// Set the trading account signer
+    pub async fn set_trading_signer(&self, signer: Arc<dyn Signer>) {
+        let mut auth = self.trading_auth.write().await;
+        *auth = auth.clone().with_signer(signer);
+    }
+
+    /// Get the trading account signer
+    pub async fn trading_signer(&self) -> Result<Arc<dyn Signer>> {
+        self.trading_auth.read().await.ensure_signer().cloned()
+    }
+
     /// Set the authentication token
     pub async fn set_token(&self, token: &str) {
         let mut auth = self.auth.write().await;
@@ -125,16 +183,13 @@ impl EkidenClient {
 
     /// Authenticate with the API using the configured private key
     pub async fn authorize(&self) -> Result<AuthorizeResponse> {
-        let auth_params = {
-            let auth = self.auth.read().await;
-            auth.generate_authorize_params()?
-        };
+        let auth = self.auth.read().await.clone();
+        let auth_params = auth.generate_authorize_params().await?;
 
         let response: AuthorizeResponse = self
             .request("authorize", RequestConfig::post(&auth_params)?)
             .await?;
 
-        println!("Authorization successful: {}", response.token);
         // Store the token
         {
             let mut auth = self.auth.write().await;
@@ -146,16 +201,13 @@ impl EkidenClient {
     }
 
     pub async fn authorize_funding(&self) -> Result<AuthorizeResponse> {
-        let auth_params = {
-            let auth = self.funding_auth.read().await;
-            auth.generate_authorize_params()?
-        };
+        let auth = self.funding_auth.read().await.clone();
+        let auth_params = auth.generate_authorize_params().await?;
 
         let response: AuthorizeResponse = self
             .request("authorize", RequestConfig::post(&auth_params)?)
             .await?;
 
-        println!("Authorization successful: {}", response.token);
         // Store the token
         {
             let mut auth = self.funding_auth.write().await;
@@ -167,16 +219,13 @@ impl EkidenClient {
     }
 
     pub async fn authorize_trading(&self) -> Result<AuthorizeResponse> {
-        let auth_params = {
-            let auth = self.trading_auth.read().await;
-            auth.generate_authorize_params()?
-        };
+        let auth = self.trading_auth.read().await.clone();
+        let auth_params = auth.generate_authorize_params().await?;
 
         let response: AuthorizeResponse = self
             .request("authorize", RequestConfig::post(&auth_params)?)
             .await?;
 
-        println!("Authorization successful: {}", response.token);
         // Store the token
         {
             let mut auth = self.trading_auth.write().await;
@@ -187,6 +236,117 @@ impl EkidenClient {
         Ok(response)
     }
 
+    /// Authenticate the key of the given role
+    pub async fn authorize_role(&self, role: KeyRole) -> Result<AuthorizeResponse> {
+        match role {
+            KeyRole::Root => self.authorize().await,
+            KeyRole::Funding => self.authorize_funding().await,
+            KeyRole::Trading => self.authorize_trading().await,
+        }
+    }
+
+    fn auth_for(&self, role: KeyRole) -> &Arc<RwLock<Auth>> {
+        match role {
+            KeyRole::Root => &self.auth,
+            KeyRole::Funding => &self.funding_auth,
+            KeyRole::Trading => &self.trading_auth,
+        }
+    }
+
+    // ===== Sessions =====
+
+    /// Snapshot the tokens and nonce state so a restarted process can resume
+    pub async fn export_session(&self) -> Session {
+        let mut session = Session::new(self.nonces.high_water());
+        for role in KeyRole::ALL {
+            let auth = self.auth_for(role).read().await;
+            if let Some(token) = auth.token() {
+                session.set_token(role, SessionToken::new(token, auth.public_key()));
+            }
+        }
+        session
+    }
+
+    /// Restore tokens and nonce state from an exported session.
+    ///
+    /// Tokens that expire within [`SESSION_MIN_TTL`], have no readable expiry
+    /// or were issued for a different key are skipped. The tokens are not
+    /// checked with the gateway, see [`restore_session`](Self::restore_session).
+    /// Returns the roles whose token was restored.
+    pub async fn import_session(&self, session: &Session) -> Vec<KeyRole> {
+        self.nonces.observe(session.nonce_high_water);
+
+        let mut restored = Vec::new();
+        for role in KeyRole::ALL {
+            let Some(token) = session.token(role) else {
+                continue;
+            };
+            let mut auth = self.auth_for(role).write().await;
+            if !token.is_unexpired(SESSION_MIN_TTL) {
+                debug!("Session token for {} key is expired", role);
+                continue;
+            }
+            if let (Some(expected), Some(current)) = (&token.public_key, auth.public_key()) {
+                if *expected != current {
+                    warn!("Session token for {} key was issued for another key", role);
+                    continue;
+                }
+            }
+            auth.set_token(token.token.expose_secret());
+            restored.push(role);
+        }
+        restored
+    }
+
+    /// Save the current session to a store
+    pub async fn save_session(&self, store: &dyn SessionStore) -> Result<()> {
+        store.save(&self.export_session().await).await
+    }
+
+    /// Resume a saved session, re-authorizing every role without a usable
+    /// token, and save the refreshed session back to the store.
+    ///
+    /// A restored trading token is checked with a cheap authenticated request
+    /// and re-authorized if the gateway rejects it. Root and funding tokens
+    /// have no such endpoint, so only their expiry is checked.
+    pub async fn restore_session(&self, store: &dyn SessionStore) -> Result<()> {
+        let mut restored = match store.load().await {
+            Ok(Some(session)) => self.import_session(&session).await,
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                warn!("Failed to load session, re-authorizing: {}", e);
+                Vec::new()
+            }
+        };
+        if restored.contains(&KeyRole::Trading) && !self.check_trading_token().await? {
+            warn!("Session token for trading key was rejected, re-authorizing");
+            restored.retain(|role| *role != KeyRole::Trading);
+        }
+        for role in KeyRole::ALL {
+            if !restored.contains(&role) {
+                self.authorize_role(role).await?;
+            }
+        }
+        self.save_session(store).await
+    }
+
+    /// Check the trading token with the gateway; `false` if it is rejected
+    async fn check_trading_token(&self) -> Result<bool> {
+        let params = ListVaultsParams {
+            pagination: Pagination {
+                limit: Some(1),
+                ..Pagination::default()
+            },
+        };
+        match self.get_user_vaults(params).await {
+            Ok(_) => Ok(true),
+            Err(EkidenError::Api {
+                status: 401 | 403, ..
+            }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
     // ===== Market Endpoints =====
 
     /// Get market information
@@ -238,11 +398,35 @@ impl EkidenClient {
                 OrderSide::Buy => "buy".to_string(),
                 OrderSide::Sell => "sell".to_string(),
             }),
+            client_order_id: None,
             pagination: pagination.unwrap_or_default(),
         };
         self.get_orders(params).await
     }
 
+    /// Find an order in a market by its client order id
+    pub async fn find_order_by_client_id(
+        &self,
+        market_addr: &str,
+        client_order_id: &str,
+    ) -> Result<Option<OrderResponse>> {
+        let params = ListOrdersParams {
+            market_addr: market_addr.to_string(),
+            side: None,
+            client_order_id: Some(client_order_id.to_string()),
+            pagination: Pagination::default(),
+        };
+        let orders = self.get_orders(params).await?;
+        Ok(orders
+            .into_iter()
+            .find(|o| o.client_order_id.as_deref() == Some(client_order_id)))
+    }
+
+    /// Get the tracker mapping client order ids to gateway sids
+    pub fn client_orders(&self) -> &ClientOrderTracker {
+        &self.client_orders
+    }
+
     // ===== Fill Endpoints =====
 
     /// Get fills (trades) for a market
@@ -326,7 +510,7 @@ impl EkidenClient {
         };
         let config = RequestConfig::get()
             .with_query(params.to_query_params())
-            .with_auth(self.token().await.unwrap_or_default());
+            .with_auth(self.trading_token().await.unwrap_or_default());
         self.request("user/leverage", config).await
     }
 
@@ -335,44 +519,229 @@ impl EkidenClient {
         &self,
         market_addr: &str,
         leverage: u64,
+    ) -> Result<LeverageResponse> {
+        self.set_user_leverage_and_margin_mode(market_addr, leverage, None)
+            .await
+    }
+
+    /// Set user leverage for a market, optionally switching between cross
+    /// and isolated margin
+    pub async fn set_user_leverage_and_margin_mode(
+        &self,
+        market_addr: &str,
+        leverage: u64,
+        is_cross: Option<bool>,
     ) -> Result<LeverageResponse> {
         let params = SetUserLeverageParams {
             market_addr: market_addr.to_string(),
             leverage,
+            is_cross,
         };
         let config =
-            RequestConfig::post(&params)?.with_auth(self.token().await.unwrap_or_default());
+            RequestConfig::post(&params)?.with_auth(self.trading_token().await.unwrap_or_default());
         self.request("user/leverage", config).await
     }
 
     /// Get user portfolio
     pub async fn get_user_portfolio(&self) -> Result<PortfolioResponse> {
         let config = RequestConfig::get().with_auth(self.trading_token().await.unwrap_or_default());
-        println!("Fetching user portfolio... {:?}", config);
         self.request("user/portfolio", config).await
     }
 
-    pub fn sign_intent(
-        &self,
-        private_key_str: &str,
-        payload: &ActionPayload,
-        nonce: u64,
-    ) -> Result<Signature> {
-        let key_pair = Ed25519PrivateKey::from_encoded_string(private_key_str).unwrap();
-        let signature = key_pair
-            .sign_intent(IntentSignatureBody {
+    /// Sign an intent with the trading account signer
+    pub async fn sign_intent(&self, payload: &ActionPayload, nonce: u64) -> Result<Signature> {
+        let signer = self.trading_signer().await?;
+        signer
+            .sign_intent(&IntentSignatureBody {
                 payload: payload.clone(),
                 nonce,
             })
-            .map_err(|e| EkidenError::auth(format!("Failed to sign intent: {}", e)))?;
-        Ok(signature)
+            .await
+            .map_err(|e| EkidenError::auth(format!("Failed to sign intent: {}", e)))
     }
 
     /// Send an intent (execute actions)
     pub async fn send_intent(&self, params: SendIntentParams) -> Result<SendIntentResponse> {
         let config =
             RequestConfig::post(&params)?.with_auth(self.trading_token().await.unwrap_or_default());
-        self.request("user/intent/commit", config).await
+        self.nonces.observe(params.nonce);
+        let response: SendIntentResponse = self.request("user/intent/commit", config).await?;
+        self.client_orders
+            .record_intent(&params.payload, &response.output);
+        Ok(response)
+    }
+
+    /// Set or clear the rate limit of the HTTP requests of this client and
+    /// its clones
+    pub fn set_rate_limit(&self, rate_limit: Option<RateLimit>) {
+        *self.rate_limiter.write().unwrap() =
+            rate_limit.map(|limit| Arc::new(RateLimiter::new(limit)));
+    }
+
+    /// Get the rate limit of the HTTP requests, if any
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limiter
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|limiter| limiter.limit())
+    }
+
+    /// Get the next intent nonce
+    pub fn next_nonce(&self) -> u64 {
+        self.nonces.next()
+    }
+
+    /// Sign an action with the trading key and send it with a fresh nonce
+    pub async fn submit_action(&self, payload: ActionPayload) -> Result<SendIntentResponse> {
+        let params = self.signed_intent(payload).await?;
+        self.send_intent(params).await
+    }
+
+    /// Sign an action with the next nonce, without sending it, e.g. to send
+    /// it through [`send_intent_safe`](Self::send_intent_safe)
+    pub async fn signed_intent(&self, payload: ActionPayload) -> Result<SendIntentParams> {
+        let nonce = self.next_nonce();
+        let signature = signature_hex(&self.sign_intent(&payload, nonce).await?);
+        Ok(SendIntentParams {
+            payload,
+            nonce,
+            signature,
+        })
+    }
+
+    /// Amend the price and size of a resting order
+    pub async fn amend_order(
+        &self,
+        sid: &str,
+        price: u64,
+        size: u64,
+    ) -> Result<SendIntentResponse> {
+        let payload = ActionPayload::OrderAmend(OrderAmendAction {
+            amends: vec![OrderAmend {
+                sid: sid.to_string(),
+                price,
+                size,
+            }],
+        });
+        self.submit_action(payload).await
+    }
+
+    /// Cancel an order and create its replacement in one intent.
+    ///
+    /// Both legs are sent as a single [`ActionPayload::OrderReplace`] action,
+    /// so the gateway commits them together or not at all.
+    pub async fn replace_order(&self, sid: &str, order: OrderCreate) -> Result<ReplaceResult> {
+        let payload = ActionPayload::OrderReplace(OrderReplaceAction {
+            cancels: vec![OrderCancel {
+                sid: sid.to_string(),
+            }],
+            orders: vec![order],
+        });
+        let response = self.submit_action(payload).await?;
+        ReplaceResult::from_response(sid, response)
+    }
+
+    /// Replace an order with separate cancel and create intents, for gateways
+    /// without [`ActionPayload::OrderReplace`].
+    ///
+    /// This is not atomic. Both legs are signed with consecutive nonces before
+    /// either is sent, and the create leg is only sent once the cancel
+    /// committed, so a failed cancel never leaves both orders resting. When the
+    /// cancel fails with an unknown outcome, the result sets `cancel_unknown`
+    /// and the order may or may not still rest.
+    pub async fn replace_order_two_legs(
+        &self,
+        sid: &str,
+        order: OrderCreate,
+    ) -> Result<ReplaceResult> {
+        let cancel = self
+            .signed_intent(ActionPayload::OrderCancel(OrderCancelAction {
+                cancels: vec![OrderCancel {
+                    sid: sid.to_string(),
+                }],
+            }))
+            .await?;
+        let create = self
+            .signed_intent(ActionPayload::OrderCreate(OrderCreateAction {
+                orders: vec![order],
+            }))
+            .await?;
+
+        let cancelled = self.send_intent(cancel).await;
+        let created = match &cancelled {
+            Ok(_) => Some(self.send_intent(create).await),
+            Err(e) => {
+                warn!(
+                    "Cancel of {} failed, not sending its replacement: {}",
+                    sid, e
+                );
+                None
+            }
+        };
+        Ok(ReplaceResult::from_legs(sid, cancelled, created))
+    }
+
+    /// Send an order create intent, retrying transient failures without
+    /// duplicating orders.
+    ///
+    /// Every order must carry a `client_order_id`. When a submission fails with
+    /// an unknown outcome, the orders are looked up by client order id before
+    /// the same signed intent is resubmitted; if any of them exists the intent
+    /// was already committed and is not sent again.
+    pub async fn send_intent_safe(&self, params: SendIntentParams) -> Result<IntentSubmission> {
+        let orders = match &params.payload {
+            ActionPayload::OrderCreate(action) => action.orders.clone(),
+            _ => {
+                return Err(EkidenError::validation(
+                    "Safe retry is only supported for order create intents",
+                ))
+            }
+        };
+        if orders.iter().any(|o| o.client_order_id.is_none()) {
+            return Err(EkidenError::validation(
+                "Every order needs a client_order_id for safe retry",
+            ));
+        }
+
+        let mut attempt = 0;
+        loop {
+            match self.send_intent(params.clone()).await {
+                Ok(response) => return Ok(IntentSubmission::Committed(response)),
+                Err(e) if e.is_retryable() && attempt < self.config.max_retries => {
+                    attempt += 1;
+                    warn!(
+                        "Intent submission failed ({}), checking orders before retry {}",
+                        e, attempt
+                    );
+                    tokio::time::sleep(self.config.retry_delay).await;
+
+                    let committed = self.find_committed_orders(&orders).await?;
+                    if !committed.is_empty() {
+                        return Ok(IntentSubmission::AlreadyCommitted(committed));
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Look up the orders of an intent by client order id, recording any that exist
+    async fn find_committed_orders(&self, orders: &[OrderCreate]) -> Result<Vec<OrderResponse>> {
+        let mut committed = vec![];
+        for order in orders {
+            let Some(client_order_id) = &order.client_order_id else {
+                continue;
+            };
+            if let Some(existing) = self
+                .find_order_by_client_id(&order.market_addr, client_order_id)
+                .await?
+            {
+                self.client_orders.record(client_order_id, &existing.sid);
+                committed.push(existing);
+            }
+        }
+        Ok(committed)
     }
 
     // ===== Deposit/Withdrawal Endpoints =====
@@ -537,6 +906,19 @@ impl EkidenClient {
         }
     }
 
+    /// Subscribe to mark price updates
+    pub async fn subscribe_mark_price(
+        &self,
+        market_addr: &str,
+    ) -> Result<tokio::sync::broadcast::Receiver<WsEvent>> {
+        if let Some(ws_client) = &self.ws_client {
+            let client = ws_client.read().await;
+            client.subscribe_mark_price(market_addr).await
+        } else {
+            Err(EkidenError::config("WebSocket client not available"))
+        }
+    }
+
     /// Unsubscribe from a channel
     pub async fn unsubscribe(&self, channel: &str) -> Result<()> {
         if let Some(ws_client) = &self.ws_client {
@@ -554,6 +936,11 @@ impl EkidenClient {
     where
         T: DeserializeOwned,
     {
+        let limiter = self.rate_limiter.read().unwrap().clone();
+        if let Some(limiter) = limiter {
+            limiter.acquire().await;
+        }
+
         let url = self.config.api_url(path);
         let auth_already_present = config.has_auth_header();
         let mut request = self.http_client.request(config.method, &url);
@@ -614,10 +1001,15 @@ impl EkidenClient {
 #[derive(Debug)]
 pub struct EkidenClientBuilder {
     config: EkidenConfig,
-    private_key: Option<String>,
-    funding_private_key: Option<String>,
-    trading_private_key: Option<String>,
-    token: Option<String>,
+    private_key: Option<SecretString>,
+    funding_private_key: Option<SecretString>,
+    trading_private_key: Option<SecretString>,
+    signer: Option<Arc<dyn Signer>>,
+    funding_signer: Option<Arc<dyn Signer>>,
+    trading_signer: Option<Arc<dyn Signer>>,
+    keystore: Option<(PathBuf, SecretString)>,
+    token: Option<SecretString>,
+    session_store: Option<Arc<dyn SessionStore>>,
 }
 
 impl EkidenClientBuilder {
@@ -628,7 +1020,12 @@ impl EkidenClientBuilder {
             private_key: None,
             funding_private_key: None,
             trading_private_key: None,
+            signer: None,
+            funding_signer: None,
+            trading_signer: None,
+            keystore: None,
             token: None,
+            session_store: None,
         }
     }
 
@@ -663,29 +1060,67 @@ impl EkidenClientBuilder {
     }
 
     /// Set the private key
-    pub fn private_key<S: Into<String>>(mut self, private_key: S) -> Self {
+    pub fn private_key<S: Into<SecretString>>(mut self, private_key: S) -> Self {
         self.private_key = Some(private_key.into());
         self
     }
 
     /// Set the trading account private key
-    pub fn trading_private_key<S: Into<String>>(mut self, private_key: S) -> Self {
+    pub fn trading_private_key<S: Into<SecretString>>(mut self, private_key: S) -> Self {
         self.trading_private_key = Some(private_key.into());
         self
     }
 
     /// Set the funding account private key
-    pub fn funding_private_key<S: Into<String>>(mut self, private_key: S) -> Self {
+    pub fn funding_private_key<S: Into<SecretString>>(mut self, private_key: S) -> Self {
         self.funding_private_key = Some(private_key.into());
         self
     }
 
+    /// Set the signer, e.g. a [`RemoteSigner`](crate::signer::RemoteSigner)
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Set the funding account signer
+    pub fn funding_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.funding_signer = Some(signer);
+        self
+    }
+
+    /// Set the trading account signer
+    pub fn trading_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.trading_signer = Some(signer);
+        self
+    }
+
+    /// Load the root, funding and trading keys from an encrypted keystore directory.
+    ///
+    /// Roles without a key file are skipped. [`build`](Self::build) fails if
+    /// the directory does not exist or holds none of the roles.
+    pub fn keystore<P: Into<PathBuf>, S: Into<SecretString>>(
+        mut self,
+        dir: P,
+        password: S,
+    ) -> Self {
+        self.keystore = Some((dir.into(), password.into()));
+        self
+    }
+
     /// Set the authentication token
-    pub fn token<S: Into<String>>(mut self, token: S) -> Self {
+    pub fn token<S: Into<SecretString>>(mut self, token: S) -> Self {
         self.token = Some(token.into());
         self
     }
 
+    /// Resume sessions from a store in [`build_and_auth`](Self::build_and_auth)
+    /// instead of always re-authorizing
+    pub fn session_store(mut self, store: Arc<dyn SessionStore>) -> Self {
+        self.session_store = Some(store);
+        self
+    }
+
     /// Set request timeout
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.config = self.config.with_timeout(timeout);
@@ -710,22 +1145,62 @@ impl EkidenClientBuilder {
 
         // Set private key if provided
         if let Some(private_key) = self.private_key {
-            client.set_private_key(&private_key).await?;
+            client.set_private_key(private_key.expose_secret()).await?;
         }
 
         // Set private key if provided
         if let Some(private_key) = self.funding_private_key {
-            client.set_funding_private_key(&private_key).await?;
+            client
+                .set_funding_private_key(private_key.expose_secret())
+                .await?;
         }
 
         // Set private key if provided
         if let Some(private_key) = self.trading_private_key {
-            client.set_trading_private_key(&private_key).await?;
+            client
+                .set_trading_private_key(private_key.expose_secret())
+                .await?;
+        }
+
+        // Keystore keys take precedence over private keys
+        if let Some((dir, password)) = self.keystore {
+            let keystore = Keystore::open_existing(dir)?;
+            if !KeyRole::ALL.iter().any(|role| keystore.exists(*role)) {
+                return Err(EkidenError::config(format!(
+                    "Keystore {} has no root, funding or trading key",
+                    keystore.dir().display()
+                )));
+            }
+            for role in KeyRole::ALL {
+                if !keystore.exists(role) {
+                    continue;
+                }
+                let signer: Arc<dyn Signer> =
+                    Arc::new(keystore.load(role, password.expose_secret())?);
+                match role {
+                    KeyRole::Root => client.set_signer(signer).await,
+                    KeyRole::Funding => client.set_funding_signer(signer).await,
+                    KeyRole::Trading => client.set_trading_signer(signer).await,
+                }
+            }
+        }
+
+        // Signers take precedence over keystore and private keys
+        if let Some(signer) = self.signer {
+            client.set_signer(signer).await;
+        }
+
+        if let Some(signer) = self.funding_signer {
+            client.set_funding_signer(signer).await;
+        }
+
+        if let Some(signer) = self.trading_signer {
+            client.set_trading_signer(signer).await;
         }
 
         // Set token if provided
         if let Some(token) = self.token {
-            client.set_token(&token).await;
+            client.set_token(token.expose_secret()).await;
         }
 
         Ok(client)
@@ -733,7 +1208,12 @@ impl EkidenClientBuilder {
 
     /// Build and authenticate the client
     pub async fn build_and_auth(self) -> Result<EkidenClient> {
+        let session_store = self.session_store.clone();
         let client = self.build().await?;
+        if let Some(store) = session_store {
+            client.restore_session(store.as_ref()).await?;
+            return Ok(client);
+        }
         client.authorize().await?;
         client.authorize_funding().await?;
         client.authorize_trading().await?;
@@ -769,4 +1249,59 @@ mod tests {
 
         assert!(!client.is_authenticated().await);
     }
+
+    #[tokio::test]
+    async fn test_session_export_import() {
+        use crate::session::tests::jwt;
+
+        let key_pair = crate::utils::KeyPair::generate();
+        let client = EkidenClient::default_config().unwrap();
+        client.set_signer(Arc::new(key_pair.clone())).await;
+        client.set_token(&jwt(3600)).await;
+        client.next_nonce();
+        let session = client.export_session().await;
+        assert_eq!(
+            session.root.as_ref().unwrap().public_key,
+            Some(key_pair.public_key())
+        );
+        assert!(session.trading.is_none());
+
+        let restored = EkidenClient::default_config().unwrap();
+        restored.set_signer(Arc::new(key_pair)).await;
+        assert_eq!(restored.import_session(&session).await, vec![KeyRole::Root]);
+        assert_eq!(restored.token().await, client.token().await);
+        assert!(restored.next_nonce() > session.nonce_high_water);
+
+        // Tokens issued for another key or about to expire are dropped
+        let other = EkidenClient::default_config().unwrap();
+        other
+            .set_signer(Arc::new(crate::utils::KeyPair::generate()))
+            .await;
+        assert!(other.import_session(&session).await.is_empty());
+
+        let mut expired = session.clone();
+        expired.root = Some(SessionToken::new(jwt(10), None));
+        let fresh = EkidenClient::default_config().unwrap();
+        assert!(fresh.import_session(&expired).await.is_empty());
+        assert!(!fresh.is_authenticated().await);
+    }
+
+    #[tokio::test]
+    async fn test_sign_intent_with_trading_signer() {
+        let client = EkidenClient::default_config().unwrap();
+        let payload = ActionPayload::OrderCancelAll(OrderCancelAllAction { market_addr: None });
+        assert!(client.sign_intent(&payload, 1).await.is_err());
+
+        let key_pair = crate::utils::KeyPair::generate();
+        client.set_trading_signer(Arc::new(key_pair.clone())).await;
+        let signature = client.sign_intent(&payload, 1).await.unwrap();
+        let message =
+            crate::signer::intent_message(&IntentSignatureBody { payload, nonce: 1 }).unwrap();
+        assert!(crate::utils::Crypto::verify_signature(
+            &message,
+            &signature_hex(&signature),
+            &key_pair.public_key()
+        )
+        .unwrap());
+    }
 }
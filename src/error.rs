@@ -54,6 +54,14 @@ pub enum EkidenError {
 
     #[error("Aptos error: {0}")]
     Aptos(String),
+
+    #[error("Transaction {hash} committed at version {version} but not indexed by the gateway after {waited:?} (last status: {last_status:?})")]
+    IndexerLag {
+        hash: String,
+        version: u64,
+        waited: std::time::Duration,
+        last_status: Option<String>,
+    },
 }
 
 impl EkidenError {
@@ -87,4 +95,15 @@ impl EkidenError {
     pub fn aptos<S: Into<String>>(msg: S) -> Self {
         Self::Aptos(msg.into())
     }
+
+    /// Whether the error may be transient, i.e. the request may have never
+    /// reached the gateway or its outcome is unknown
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Http(e) => e.is_timeout() || e.is_connect(),
+            Self::Network(_) | Self::Timeout | Self::ConnectionClosed => true,
+            Self::Api { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
 }
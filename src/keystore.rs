@@ -0,0 +1,483 @@
+use crate::error::{EkidenError, Result};
+use crate::utils::KeyPair;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::{thread_rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+const KEYSTORE_VERSION: u32 = 1;
+const CIPHER: &str = "aes-256-gcm";
+const KDF: &str = "scrypt";
+
+/// Role of a key in an Ekiden account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyRole {
+    Root,
+    Funding,
+    Trading,
+}
+
+impl KeyRole {
+    pub const ALL: [KeyRole; 3] = [KeyRole::Root, KeyRole::Funding, KeyRole::Trading];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyRole::Root => "root",
+            KeyRole::Funding => "funding",
+            KeyRole::Trading => "trading",
+        }
+    }
+
+    /// File name of the role's key in a keystore directory
+    pub fn file_name(&self) -> String {
+        format!("{}.json", self.as_str())
+    }
+}
+
+impl Display for KeyRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Scrypt cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for ScryptParams {
+    fn default() -> Self {
+        Self {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    #[serde(flatten)]
+    pub scrypt: ScryptParams,
+    pub salt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CryptoParams {
+    pub cipher: String,
+    pub ciphertext: String,
+    pub nonce: String,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+}
+
+/// A password-encrypted key file.
+///
+/// The private key is encrypted with AES-256-GCM under a key derived from
+/// the password with scrypt. The public key is stored in the clear and
+/// authenticated as associated data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeystoreFile {
+    pub version: u32,
+    pub public_key: String,
+    pub crypto: CryptoParams,
+}
+
+impl KeystoreFile {
+    /// Encrypt a key pair with a password
+    pub fn encrypt(key_pair: &KeyPair, password: &str, params: ScryptParams) -> Result<Self> {
+        let mut salt = [0u8; 32];
+        let mut nonce = [0u8; 12];
+        thread_rng().fill_bytes(&mut salt);
+        thread_rng().fill_bytes(&mut nonce);
+
+        let public_key = key_pair.public_key();
+        let private_key = Zeroizing::new(key_pair.get_private_key().to_bytes());
+        let cipher = cipher(password, &salt, params)?;
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: private_key.as_slice(),
+                    aad: public_key.as_bytes(),
+                },
+            )
+            .map_err(|_| EkidenError::crypto("Failed to encrypt key"))?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            public_key,
+            crypto: CryptoParams {
+                cipher: CIPHER.to_string(),
+                ciphertext: hex::encode(ciphertext),
+                nonce: hex::encode(nonce),
+                kdf: KDF.to_string(),
+                kdfparams: KdfParams {
+                    scrypt: params,
+                    salt: hex::encode(salt),
+                },
+            },
+        })
+    }
+
+    /// Decrypt the key pair with a password
+    pub fn decrypt(&self, password: &str) -> Result<KeyPair> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(EkidenError::crypto(format!(
+                "Unsupported keystore version {}",
+                self.version
+            )));
+        }
+        if self.crypto.cipher != CIPHER || self.crypto.kdf != KDF {
+            return Err(EkidenError::crypto(format!(
+                "Unsupported keystore cipher {} with kdf {}",
+                self.crypto.cipher, self.crypto.kdf
+            )));
+        }
+
+        let salt = decode_hex(&self.crypto.kdfparams.salt)?;
+        let nonce = decode_hex(&self.crypto.nonce)?;
+        let ciphertext = decode_hex(&self.crypto.ciphertext)?;
+        if nonce.len() != 12 {
+            return Err(EkidenError::crypto("Keystore nonce must be 12 bytes"));
+        }
+
+        let cipher = cipher(password, &salt, self.crypto.kdfparams.scrypt)?;
+        let private_key = Zeroizing::new(
+            cipher
+                .decrypt(
+                    Nonce::from_slice(&nonce),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: self.public_key.as_bytes(),
+                    },
+                )
+                .map_err(|_| EkidenError::crypto("Invalid keystore password"))?,
+        );
+
+        let key_pair = KeyPair::from_bytes(&private_key)?;
+        if key_pair.public_key() != self.public_key {
+            return Err(EkidenError::crypto("Keystore public key does not match"));
+        }
+        Ok(key_pair)
+    }
+
+    /// Read a key file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Write a key file, readable by the owner only
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        write_private(path.as_ref(), &serde_json::to_vec_pretty(self)?)
+    }
+}
+
+/// Directory of encrypted root, funding and trading keys
+#[derive(Debug, Clone)]
+pub struct Keystore {
+    dir: PathBuf,
+    params: ScryptParams,
+}
+
+impl Keystore {
+    /// Open a keystore directory, creating it if needed
+    pub fn open<P: Into<PathBuf>>(dir: P) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            params: ScryptParams::default(),
+        })
+    }
+
+    /// Open an existing keystore directory without creating it
+    pub fn open_existing<P: Into<PathBuf>>(dir: P) -> Result<Self> {
+        let dir = dir.into();
+        if !dir.is_dir() {
+            return Err(EkidenError::config(format!(
+                "Keystore directory {} does not exist",
+                dir.display()
+            )));
+        }
+        Ok(Self {
+            dir,
+            params: ScryptParams::default(),
+        })
+    }
+
+    /// Set the scrypt parameters for newly encrypted keys
+    pub fn with_scrypt_params(mut self, params: ScryptParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Get the keystore directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get the path of a role's key file
+    pub fn path(&self, role: KeyRole) -> PathBuf {
+        self.dir.join(role.file_name())
+    }
+
+    /// Check if a role's key is stored
+    pub fn exists(&self, role: KeyRole) -> bool {
+        self.path(role).exists()
+    }
+
+    /// Get the public key of a stored role without decrypting it
+    pub fn public_key(&self, role: KeyRole) -> Result<String> {
+        Ok(KeystoreFile::load(self.path(role))?.public_key)
+    }
+
+    /// Encrypt and store a role's key, replacing any existing one
+    pub fn save(&self, role: KeyRole, key_pair: &KeyPair, password: &str) -> Result<()> {
+        KeystoreFile::encrypt(key_pair, password, self.params)?.save(self.path(role))
+    }
+
+    /// Load and decrypt a role's key
+    pub fn load(&self, role: KeyRole, password: &str) -> Result<KeyPair> {
+        let path = self.path(role);
+        if !path.exists() {
+            return Err(EkidenError::config(format!(
+                "No {} key in keystore {}",
+                role,
+                self.dir.display()
+            )));
+        }
+        KeystoreFile::load(path)?.decrypt(password)
+    }
+
+    /// Re-encrypt a role's key with a new password
+    pub fn change_password(&self, role: KeyRole, password: &str, new_password: &str) -> Result<()> {
+        let key_pair = self.load(role, password)?;
+        self.save(role, &key_pair, new_password)
+    }
+
+    /// Replace a role's key with a newly generated one.
+    ///
+    /// The previous key file is kept next to it with a timestamp suffix, as
+    /// the old key may still be linked on-chain.
+    pub fn rotate(&self, role: KeyRole, password: &str) -> Result<KeyPair> {
+        let path = self.path(role);
+        if path.exists() {
+            // Make sure the password is right before touching anything
+            self.load(role, password)?;
+
+            self.backup(role)?;
+        }
+
+        let key_pair = KeyPair::generate();
+        self.save(role, &key_pair, password)?;
+        Ok(key_pair)
+    }
+
+    /// Copy a role's key file to a new `{role}.{nanos}.{n}.json` backup,
+    /// never overwriting an existing file
+    fn backup(&self, role: KeyRole) -> Result<PathBuf> {
+        let contents = std::fs::read(self.path(role))?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+
+        for counter in 0u32.. {
+            let backup = self
+                .dir
+                .join(format!("{}.{}.{}.json", role.as_str(), timestamp, counter));
+            match create_private(&backup) {
+                Ok(mut file) => {
+                    use std::io::Write;
+                    file.write_all(&contents)?;
+                    file.sync_all()?;
+                    return Ok(backup);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        unreachable!("backup counter exhausted")
+    }
+}
+
+/// Atomically write a file readable by the owner only.
+///
+/// The contents go to a new temporary file next to `path`, created with mode
+/// 0600 and a unique name so an existing file or symlink is never followed,
+/// and synced before it replaces `path`.
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+    let tmp = PathBuf::from(tmp);
+
+    let mut file = create_private(&tmp)?;
+    let written = {
+        use std::io::Write;
+        file.write_all(contents)
+            .and_then(|_| file.sync_all())
+            .and_then(|_| std::fs::rename(&tmp, path))
+    };
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    written?;
+    Ok(())
+}
+
+/// Create a new file readable by the owner only, failing if it exists
+fn create_private(path: &Path) -> std::io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+fn cipher(password: &str, salt: &[u8], params: ScryptParams) -> Result<Aes256Gcm> {
+    let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+        .map_err(|e| EkidenError::crypto(format!("Invalid scrypt parameters: {}", e)))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    scrypt::scrypt(password.as_bytes(), salt, &scrypt_params, key.as_mut())
+        .map_err(|e| EkidenError::crypto(format!("Failed to derive key: {}", e)))?;
+    Aes256Gcm::new_from_slice(key.as_slice()).map_err(|e| EkidenError::crypto(e.to_string()))
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .map_err(|_| EkidenError::crypto("Invalid hex in keystore"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAST: ScryptParams = ScryptParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    fn keystore() -> Keystore {
+        let dir = std::env::temp_dir().join(format!("ekiden-keystore-{}", uuid::Uuid::new_v4()));
+        Keystore::open(dir).unwrap().with_scrypt_params(FAST)
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let key_pair = KeyPair::generate();
+        let file = KeystoreFile::encrypt(&key_pair, "password", FAST).unwrap();
+        assert_eq!(file.public_key, key_pair.public_key());
+        assert!(!file
+            .crypto
+            .ciphertext
+            .contains(&key_pair.private_key().expose_secret()[2..]));
+
+        let decrypted = file.decrypt("password").unwrap();
+        assert_eq!(decrypted.private_key(), key_pair.private_key());
+        assert!(file.decrypt("wrong").is_err());
+    }
+
+    #[test]
+    fn test_keystore_roles() {
+        let keystore = keystore();
+        let trading = KeyPair::generate();
+        assert!(!keystore.exists(KeyRole::Trading));
+        assert!(keystore.load(KeyRole::Trading, "password").is_err());
+
+        keystore
+            .save(KeyRole::Trading, &trading, "password")
+            .unwrap();
+        assert!(keystore.exists(KeyRole::Trading));
+        assert!(!keystore.exists(KeyRole::Funding));
+        assert_eq!(
+            keystore.public_key(KeyRole::Trading).unwrap(),
+            trading.public_key()
+        );
+
+        keystore
+            .change_password(KeyRole::Trading, "password", "new")
+            .unwrap();
+        assert!(keystore.load(KeyRole::Trading, "password").is_err());
+        let loaded = keystore.load(KeyRole::Trading, "new").unwrap();
+        assert_eq!(loaded.private_key(), trading.private_key());
+
+        std::fs::remove_dir_all(keystore.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_rotate() {
+        let keystore = keystore();
+        let old = KeyPair::generate();
+        keystore.save(KeyRole::Funding, &old, "password").unwrap();
+
+        assert!(keystore.rotate(KeyRole::Funding, "wrong").is_err());
+        let new = keystore.rotate(KeyRole::Funding, "password").unwrap();
+        assert_ne!(new.public_key(), old.public_key());
+        assert_eq!(
+            keystore.public_key(KeyRole::Funding).unwrap(),
+            new.public_key()
+        );
+
+        // The previous key is kept as a backup
+        let files = std::fs::read_dir(keystore.dir()).unwrap().count();
+        assert_eq!(files, 2);
+
+        // Back-to-back rotations never overwrite a backup
+        keystore.rotate(KeyRole::Funding, "password").unwrap();
+        keystore.rotate(KeyRole::Funding, "password").unwrap();
+        let files = std::fs::read_dir(keystore.dir()).unwrap().count();
+        assert_eq!(files, 4);
+
+        std::fs::remove_dir_all(keystore.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_open_existing() {
+        let dir = std::env::temp_dir().join(format!("ekiden-keystore-{}", uuid::Uuid::new_v4()));
+        assert!(Keystore::open_existing(&dir).is_err());
+        assert!(!dir.exists());
+
+        let keystore = Keystore::open(&dir).unwrap();
+        assert!(Keystore::open_existing(&dir).is_ok());
+        std::fs::remove_dir_all(keystore.dir()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let keystore = keystore();
+        let path = keystore.dir().join("secret.json");
+        let victim = keystore.dir().join("victim");
+        std::fs::write(&victim, b"untouched").unwrap();
+        // A planted link at the old predictable temp path is never followed
+        std::os::unix::fs::symlink(&victim, keystore.dir().join("secret.json.tmp")).unwrap();
+
+        write_private(&path, b"first").unwrap();
+        write_private(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(std::fs::read(&victim).unwrap(), b"untouched");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // No temporary files are left behind
+        let files = std::fs::read_dir(keystore.dir()).unwrap().count();
+        assert_eq!(files, 3);
+
+        std::fs::remove_dir_all(keystore.dir()).unwrap();
+    }
+}
@@ -1,21 +1,55 @@
 #![allow(clippy::result_large_err)]
 #![allow(clippy::large_enum_variant)]
+pub mod account;
 pub mod auth;
+pub mod batcher;
 pub mod client;
 pub mod config;
 pub mod error;
+pub mod keystore;
+pub mod mnemonic;
+pub mod nonce;
+pub mod orders;
+pub mod pool;
+pub mod rate_limit;
+pub mod secret;
+pub mod session;
+pub mod signer;
+pub mod tracker;
+pub mod triggers;
 pub mod types;
 pub mod utils;
 pub mod ws;
 
 // Re-export main types for convenience
+pub use account::{AccountSettings, MarginMode, MarketSettings};
 pub use auth::Auth;
+pub use batcher::{BatcherConfig, IntentBatcher};
 pub use client::{EkidenClient, EkidenClientBuilder};
 pub use config::EkidenConfig;
 pub use error::{EkidenError, Result};
+pub use keystore::{KeyRole, Keystore};
+pub use mnemonic::{AccountKeys, AccountMnemonic};
+pub use nonce::NonceManager;
+pub use orders::{
+    generate_client_order_id, ClientOrderTracker, IntentSubmission, OrderBuilder, ReplaceResult,
+};
+pub use pool::{AccountPool, AggregatedPortfolio};
+pub use rate_limit::{RateLimit, RateLimiter};
+pub use secret::SecretString;
+pub use session::{FileSessionStore, Session, SessionStore};
+pub use signer::{RemoteSigner, Signer};
+pub use tracker::{TrackerConfig, TransferKind, TransferStatus, TransferTracker};
+pub use triggers::{TriggerEngine, TriggerFired};
 pub use types::*;
 pub use utils::{Crypto, KeyPair};
 
 pub mod aptos;
 
+pub use aptos::entry::{EntryFunctionSpec, MoveType, MoveValue};
+pub use aptos::error::VaultError;
+pub use aptos::events::EkidenEvent;
+pub use aptos::multisig::MultiEd25519Account;
+pub use aptos::offline::TransactionParams;
+pub use aptos::vault::{PollBackoff, TransactionEvent, TransactionOutcome, TransactionReceipt};
 pub use aptos::*;
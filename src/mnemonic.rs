@@ -0,0 +1,276 @@
+use crate::error::{EkidenError, Result};
+use crate::keystore::{KeyRole, Keystore};
+use crate::secret::{SecretString, REDACTED};
+use crate::utils::KeyPair;
+use hmac::{Hmac, Mac};
+use rand::{thread_rng, RngCore};
+use sha2::Sha512;
+use std::fmt::{Debug, Formatter};
+use zeroize::Zeroizing;
+
+/// Aptos coin type registered in SLIP-0044
+pub const APTOS_COIN_TYPE: u32 = 637;
+
+const HARDENED: u32 = 0x8000_0000;
+const ED25519_SEED_KEY: &[u8] = b"ed25519 seed";
+
+/// BIP-39 backup phrase for an Ekiden account.
+///
+/// All three role keys are derived from it along Aptos-compatible SLIP-0010
+/// paths, so the root key matches the one Aptos wallets derive from the same
+/// phrase.
+#[derive(Clone)]
+pub struct AccountMnemonic {
+    mnemonic: bip39::Mnemonic,
+    passphrase: SecretString,
+}
+
+impl Debug for AccountMnemonic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccountMnemonic")
+            .field("word_count", &self.mnemonic.word_count())
+            .field("phrase", &REDACTED)
+            .finish()
+    }
+}
+
+impl AccountMnemonic {
+    /// Generate a new random phrase with 12, 15, 18, 21 or 24 words
+    pub fn generate(word_count: usize) -> Result<Self> {
+        if !(12..=24).contains(&word_count) || word_count % 3 != 0 {
+            return Err(EkidenError::validation(format!(
+                "Invalid mnemonic word count: {}",
+                word_count
+            )));
+        }
+        let mut entropy = Zeroizing::new([0u8; 32]);
+        let entropy = &mut entropy[..word_count / 3 * 4];
+        thread_rng().fill_bytes(entropy);
+        let mnemonic = bip39::Mnemonic::from_entropy(entropy)
+            .map_err(|e| EkidenError::crypto(format!("Failed to create mnemonic: {}", e)))?;
+        Ok(Self {
+            mnemonic,
+            passphrase: SecretString::default(),
+        })
+    }
+
+    /// Import an existing English phrase, checking its checksum
+    pub fn from_phrase(phrase: &str) -> Result<Self> {
+        let mnemonic = bip39::Mnemonic::parse(phrase)
+            .map_err(|e| EkidenError::validation(format!("Invalid mnemonic: {}", e)))?;
+        Ok(Self {
+            mnemonic,
+            passphrase: SecretString::default(),
+        })
+    }
+
+    /// Set an optional BIP-39 passphrase
+    pub fn with_passphrase<S: Into<SecretString>>(mut self, passphrase: S) -> Self {
+        self.passphrase = passphrase.into();
+        self
+    }
+
+    /// Export the phrase for backup
+    pub fn phrase(&self) -> SecretString {
+        SecretString::new(self.mnemonic.to_string())
+    }
+
+    /// Number of words in the phrase
+    pub fn word_count(&self) -> usize {
+        self.mnemonic.word_count()
+    }
+
+    /// Derive the key of a single role for the given account index
+    pub fn derive_key(&self, account: u32, role: KeyRole) -> Result<KeyPair> {
+        let seed = Zeroizing::new(self.mnemonic.to_seed(self.passphrase.expose_secret()));
+        derive_key_pair(seed.as_slice(), &derivation_path(account, role))
+    }
+
+    /// Derive the root, funding and trading keys for the given account index
+    pub fn derive_account(&self, account: u32) -> Result<AccountKeys> {
+        Ok(AccountKeys {
+            root: self.derive_key(account, KeyRole::Root)?,
+            funding: self.derive_key(account, KeyRole::Funding)?,
+            trading: self.derive_key(account, KeyRole::Trading)?,
+        })
+    }
+}
+
+/// Root, funding and trading keys of one Ekiden account
+#[derive(Debug, Clone)]
+pub struct AccountKeys {
+    pub root: KeyPair,
+    pub funding: KeyPair,
+    pub trading: KeyPair,
+}
+
+impl AccountKeys {
+    /// Get the key of a role
+    pub fn get(&self, role: KeyRole) -> &KeyPair {
+        match role {
+            KeyRole::Root => &self.root,
+            KeyRole::Funding => &self.funding,
+            KeyRole::Trading => &self.trading,
+        }
+    }
+
+    /// Encrypt and store all three keys, replacing any existing ones
+    pub fn save(&self, keystore: &Keystore, password: &str) -> Result<()> {
+        for role in KeyRole::ALL {
+            keystore.save(role, self.get(role), password)?;
+        }
+        Ok(())
+    }
+}
+
+/// Derivation path of a role's key: `m/44'/637'/{account}'/0'/{role}'`.
+///
+/// The root key uses the default Aptos wallet path, funding and trading keys
+/// follow it at indexes 1 and 2.
+pub fn derivation_path(account: u32, role: KeyRole) -> String {
+    let index = match role {
+        KeyRole::Root => 0,
+        KeyRole::Funding => 1,
+        KeyRole::Trading => 2,
+    };
+    format!("m/44'/{}'/{}'/0'/{}'", APTOS_COIN_TYPE, account, index)
+}
+
+/// Derive an ed25519 key pair from a BIP-39 seed along a SLIP-0010 path.
+///
+/// Ed25519 only supports hardened derivation, so every path segment must
+/// end with `'`.
+pub fn derive_key_pair(seed: &[u8], path: &str) -> Result<KeyPair> {
+    let (mut key, mut chain_code) = hmac_sha512(ED25519_SEED_KEY, &[seed]);
+    for index in parse_path(path)? {
+        (key, chain_code) = hmac_sha512(
+            chain_code.as_slice(),
+            &[&[0u8][..], key.as_slice(), &index.to_be_bytes()],
+        );
+    }
+    KeyPair::from_bytes(key.as_slice())
+}
+
+fn parse_path(path: &str) -> Result<Vec<u32>> {
+    let invalid = || EkidenError::validation(format!("Invalid derivation path: {}", path));
+    let mut segments = path.split('/');
+    if segments.next() != Some("m") {
+        return Err(invalid());
+    }
+    segments
+        .map(|segment| {
+            let index: u32 = segment
+                .strip_suffix('\'')
+                .ok_or_else(invalid)?
+                .parse()
+                .map_err(|_| invalid())?;
+            if index >= HARDENED {
+                return Err(invalid());
+            }
+            Ok(index | HARDENED)
+        })
+        .collect()
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> (Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>) {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for chunk in data {
+        mac.update(chunk);
+    }
+    let mut output = Zeroizing::new([0u8; 64]);
+    output.copy_from_slice(&mac.finalize().into_bytes());
+    let mut left = Zeroizing::new([0u8; 32]);
+    let mut right = Zeroizing::new([0u8; 32]);
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    (left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_slip10_vector() {
+        // SLIP-0010 ed25519 test vector 1
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let cases = [
+            (
+                "m",
+                "0x2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+            ),
+            (
+                "m/0'",
+                "0x68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+            ),
+            (
+                "m/0'/1'/2'/2'/1000000000'",
+                "0x8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+            ),
+        ];
+        for (path, expected) in cases {
+            let key_pair = derive_key_pair(&seed, path).unwrap();
+            assert_eq!(key_pair.private_key().expose_secret(), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        let seed = [0u8; 64];
+        for path in ["", "0'", "m/0", "m/44'/x'", "m/2147483648'"] {
+            assert!(derive_key_pair(&seed, path).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_derive_account() {
+        let mnemonic = AccountMnemonic::from_phrase(PHRASE).unwrap();
+        assert_eq!(derivation_path(0, KeyRole::Funding), "m/44'/637'/0'/0'/1'");
+
+        let keys = mnemonic.derive_account(0).unwrap();
+        assert_eq!(
+            keys.root.private_key().expose_secret(),
+            "0xcc92c0eaf80206d817f150e21917f797e49cf644a33ac514de3c316baa2f1bf5"
+        );
+        assert_eq!(
+            keys.funding.private_key().expose_secret(),
+            "0x3dc7bcd2c8186e6728c76e26a003ab18e8797332c964297bc7a8a4704a897646"
+        );
+        assert_eq!(
+            keys.trading.private_key().expose_secret(),
+            "0xa7604c5e9150fa08b79b6e9d4c1e8e240a19187da99dffb67b98f41f302401a5"
+        );
+
+        // Other accounts and passphrases give unrelated keys
+        let other = mnemonic.derive_account(1).unwrap();
+        assert_ne!(other.root.public_key(), keys.root.public_key());
+        let protected = mnemonic
+            .with_passphrase("secret")
+            .derive_account(0)
+            .unwrap();
+        assert_ne!(protected.root.public_key(), keys.root.public_key());
+    }
+
+    #[test]
+    fn test_generate_and_restore() {
+        let mnemonic = AccountMnemonic::generate(24).unwrap();
+        assert_eq!(mnemonic.word_count(), 24);
+        assert!(!format!("{:?}", mnemonic).contains(mnemonic.phrase().expose_secret()));
+
+        let restored = AccountMnemonic::from_phrase(mnemonic.phrase().expose_secret()).unwrap();
+        let keys = mnemonic.derive_account(0).unwrap();
+        let restored_keys = restored.derive_account(0).unwrap();
+        for role in KeyRole::ALL {
+            assert_eq!(
+                keys.get(role).public_key(),
+                restored_keys.get(role).public_key()
+            );
+        }
+
+        assert!(AccountMnemonic::generate(13).is_err());
+        assert!(AccountMnemonic::from_phrase("abandon abandon abandon").is_err());
+        assert!(AccountMnemonic::from_phrase(&PHRASE.replace("about", "abandon")).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Issues strictly increasing intent nonces.
+///
+/// Nonces follow the wall clock in milliseconds, but never repeat or go
+/// backwards when several intents are signed within the same millisecond.
+#[derive(Debug, Default)]
+pub struct NonceManager {
+    last: AtomicU64,
+}
+
+impl NonceManager {
+    /// Create a new nonce manager
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a nonce manager that continues above a previously used nonce
+    pub fn with_high_water(high_water: u64) -> Self {
+        Self {
+            last: AtomicU64::new(high_water),
+        }
+    }
+
+    /// Get the next nonce
+    pub fn next(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let mut last = self.last.load(Ordering::Acquire);
+        loop {
+            let next = now.max(last + 1);
+            match self
+                .last
+                .compare_exchange_weak(last, next, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return next,
+                Err(actual) => last = actual,
+            }
+        }
+    }
+
+    /// Get the highest nonce issued or observed so far
+    pub fn high_water(&self) -> u64 {
+        self.last.load(Ordering::Acquire)
+    }
+
+    /// Record a nonce used outside of this manager, so it is never reissued
+    pub fn observe(&self, nonce: u64) {
+        self.last.fetch_max(nonce, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonces_increase() {
+        let nonces = NonceManager::new();
+        let first = nonces.next();
+        let second = nonces.next();
+        assert!(second > first);
+        assert_eq!(nonces.high_water(), second);
+    }
+
+    #[test]
+    fn test_observe_and_high_water() {
+        let future = u64::MAX / 2;
+        let nonces = NonceManager::new();
+        nonces.observe(future);
+        assert_eq!(nonces.next(), future + 1);
+
+        let nonces = NonceManager::with_high_water(future);
+        assert_eq!(nonces.next(), future + 1);
+    }
+}
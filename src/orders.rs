@@ -0,0 +1,680 @@
+use crate::error::{EkidenError, Result};
+use crate::types::{
+    ActionPayload, IntentOutput, OrderCreate, OrderCreateOutput, OrderResponse, OrderSide,
+    OrderType, SendIntentResponse, TimeInForce,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Generate a fresh client order id
+pub fn generate_client_order_id() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// Tracks which client order ids map to which gateway order `sid`s
+#[derive(Debug, Clone, Default)]
+pub struct ClientOrderTracker {
+    sids: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl ClientOrderTracker {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `client_order_id` was assigned `sid` by the gateway
+    pub fn record<C: Into<String>, S: Into<String>>(&self, client_order_id: C, sid: S) {
+        self.sids
+            .write()
+            .unwrap()
+            .insert(client_order_id.into(), sid.into());
+    }
+
+    /// Record the sids of every client-identified order in a committed intent.
+    ///
+    /// Outputs are matched by their echoed `client_order_id`. Only when the
+    /// gateway echoes none, and returns one output per order, are they
+    /// matched by position; otherwise nothing is recorded.
+    pub fn record_intent(&self, payload: &ActionPayload, output: &IntentOutput) {
+        let (orders, outputs) = match (payload, output) {
+            (ActionPayload::OrderCreate(action), IntentOutput::OrderCreate(output)) => {
+                (&action.orders, &output.outputs)
+            }
+            (ActionPayload::OrderReplace(action), IntentOutput::OrderReplace(output)) => {
+                (&action.orders, &output.orders)
+            }
+            _ => return,
+        };
+
+        let mut sids = self.sids.write().unwrap();
+        if outputs
+            .iter()
+            .any(|output| output.client_order_id.is_some())
+        {
+            for output in outputs {
+                if let Some(client_order_id) = &output.client_order_id {
+                    sids.insert(client_order_id.clone(), output.sid.clone());
+                }
+            }
+        } else if orders.len() == outputs.len() {
+            for (order, output) in orders.iter().zip(outputs.iter()) {
+                if let Some(client_order_id) = &order.client_order_id {
+                    sids.insert(client_order_id.clone(), output.sid.clone());
+                }
+            }
+        } else {
+            warn!(
+                "Intent returned {} outputs for {} orders, not recording client order ids",
+                outputs.len(),
+                orders.len()
+            );
+        }
+    }
+
+    /// Get the sid assigned to a client order id
+    pub fn sid(&self, client_order_id: &str) -> Option<String> {
+        self.sids.read().unwrap().get(client_order_id).cloned()
+    }
+
+    /// Get the client order id of a gateway order sid
+    pub fn client_order_id(&self, sid: &str) -> Option<String> {
+        self.sids
+            .read()
+            .unwrap()
+            .iter()
+            .find(|(_, s)| s.as_str() == sid)
+            .map(|(client_order_id, _)| client_order_id.clone())
+    }
+
+    /// Stop tracking a client order id
+    pub fn remove(&self, client_order_id: &str) -> Option<String> {
+        self.sids.write().unwrap().remove(client_order_id)
+    }
+
+    /// Number of tracked orders
+    pub fn len(&self) -> usize {
+        self.sids.read().unwrap().len()
+    }
+
+    /// Check if no orders are tracked
+    pub fn is_empty(&self) -> bool {
+        self.sids.read().unwrap().is_empty()
+    }
+}
+
+/// Builder for [`OrderCreate`], including conditional and reduce-only orders
+#[derive(Debug, Clone)]
+pub struct OrderBuilder {
+    market_addr: String,
+    side: OrderSide,
+    order_type: OrderType,
+    size: u64,
+    price: u64,
+    trigger_price: Option<u64>,
+    leverage: u64,
+    is_cross: bool,
+    reduce_only: bool,
+    time_in_force: Option<TimeInForce>,
+    client_order_id: Option<String>,
+}
+
+impl OrderBuilder {
+    /// Create a new order builder, defaulting to a cross-margin limit order with 1x leverage
+    pub fn new<S: Into<String>>(market_addr: S, side: OrderSide) -> Self {
+        Self {
+            market_addr: market_addr.into(),
+            side,
+            order_type: OrderType::Limit,
+            size: 0,
+            price: 0,
+            trigger_price: None,
+            leverage: 1,
+            is_cross: true,
+            reduce_only: false,
+            time_in_force: None,
+            client_order_id: None,
+        }
+    }
+
+    /// Create a limit order
+    pub fn limit<S: Into<String>>(market_addr: S, side: OrderSide, size: u64, price: u64) -> Self {
+        Self::new(market_addr, side).size(size).price(price)
+    }
+
+    /// Create a market order
+    pub fn market<S: Into<String>>(market_addr: S, side: OrderSide, size: u64) -> Self {
+        Self::new(market_addr, side)
+            .order_type(OrderType::Market)
+            .size(size)
+    }
+
+    /// Create a stop-market order
+    pub fn stop_market<S: Into<String>>(
+        market_addr: S,
+        side: OrderSide,
+        size: u64,
+        trigger_price: u64,
+    ) -> Self {
+        Self::market(market_addr, side, size)
+            .order_type(OrderType::StopMarket)
+            .trigger_price(trigger_price)
+    }
+
+    /// Create a stop-limit order
+    pub fn stop_limit<S: Into<String>>(
+        market_addr: S,
+        side: OrderSide,
+        size: u64,
+        price: u64,
+        trigger_price: u64,
+    ) -> Self {
+        Self::limit(market_addr, side, size, price)
+            .order_type(OrderType::StopLimit)
+            .trigger_price(trigger_price)
+    }
+
+    /// Create a take-profit market order
+    pub fn take_profit_market<S: Into<String>>(
+        market_addr: S,
+        side: OrderSide,
+        size: u64,
+        trigger_price: u64,
+    ) -> Self {
+        Self::market(market_addr, side, size)
+            .order_type(OrderType::TakeProfitMarket)
+            .trigger_price(trigger_price)
+    }
+
+    /// Create a take-profit limit order
+    pub fn take_profit_limit<S: Into<String>>(
+        market_addr: S,
+        side: OrderSide,
+        size: u64,
+        price: u64,
+        trigger_price: u64,
+    ) -> Self {
+        Self::limit(market_addr, side, size, price)
+            .order_type(OrderType::TakeProfitLimit)
+            .trigger_price(trigger_price)
+    }
+
+    /// Set the order type
+    pub fn order_type(mut self, order_type: OrderType) -> Self {
+        self.order_type = order_type;
+        self
+    }
+
+    /// Set the order size
+    pub fn size(mut self, size: u64) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Set the limit price
+    pub fn price(mut self, price: u64) -> Self {
+        self.price = price;
+        self
+    }
+
+    /// Set the trigger price of a conditional order
+    pub fn trigger_price(mut self, trigger_price: u64) -> Self {
+        self.trigger_price = Some(trigger_price);
+        self
+    }
+
+    /// Set the leverage
+    pub fn leverage(mut self, leverage: u64) -> Self {
+        self.leverage = leverage;
+        self
+    }
+
+    /// Use cross margin
+    pub fn cross(mut self) -> Self {
+        self.is_cross = true;
+        self
+    }
+
+    /// Use isolated margin
+    pub fn isolated(mut self) -> Self {
+        self.is_cross = false;
+        self
+    }
+
+    /// Only allow the order to reduce an existing position
+    pub fn reduce_only(mut self, reduce_only: bool) -> Self {
+        self.reduce_only = reduce_only;
+        self
+    }
+
+    /// Set the time in force
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+
+    /// Set the client order id
+    pub fn client_order_id<S: Into<String>>(mut self, client_order_id: S) -> Self {
+        self.client_order_id = Some(client_order_id.into());
+        self
+    }
+
+    /// Validate and build the order
+    pub fn build(self) -> Result<OrderCreate> {
+        if self.size == 0 {
+            return Err(EkidenError::validation("Order size must be positive"));
+        }
+        if self.leverage == 0 {
+            return Err(EkidenError::validation("Leverage must be positive"));
+        }
+        if self.order_type.has_limit_price() && self.price == 0 {
+            return Err(EkidenError::validation(format!(
+                "A {} order requires a price",
+                self.order_type
+            )));
+        }
+        match (self.order_type.is_conditional(), self.trigger_price) {
+            (true, None) | (true, Some(0)) => {
+                return Err(EkidenError::validation(format!(
+                    "A {} order requires a trigger price",
+                    self.order_type
+                )))
+            }
+            (false, Some(_)) => {
+                return Err(EkidenError::validation(format!(
+                    "A {} order cannot have a trigger price",
+                    self.order_type
+                )))
+            }
+            _ => {}
+        }
+
+        Ok(OrderCreate {
+            side: self.side.to_string(),
+            size: self.size,
+            price: self.price,
+            leverage: self.leverage,
+            r#type: self.order_type.to_string(),
+            market_addr: self.market_addr,
+            is_cross: self.is_cross,
+            time_in_force: self.time_in_force,
+            client_order_id: self.client_order_id,
+            trigger_price: self.trigger_price,
+            reduce_only: self.reduce_only,
+        })
+    }
+}
+
+/// Result of a safe-retry intent submission
+#[derive(Debug, Clone)]
+pub enum IntentSubmission {
+    /// The gateway committed the intent and returned its output
+    Committed(SendIntentResponse),
+    /// The outcome of an earlier attempt was unknown, but its orders were
+    /// found on the gateway, so the intent was not submitted again
+    AlreadyCommitted(Vec<OrderResponse>),
+}
+
+impl IntentSubmission {
+    /// Sids of the orders created by the intent
+    pub fn sids(&self) -> Vec<String> {
+        match self {
+            IntentSubmission::Committed(response) => match &response.output {
+                IntentOutput::OrderCreate(output) => {
+                    output.outputs.iter().map(|o| o.sid.clone()).collect()
+                }
+                _ => vec![],
+            },
+            IntentSubmission::AlreadyCommitted(orders) => {
+                orders.iter().map(|o| o.sid.clone()).collect()
+            }
+        }
+    }
+}
+
+/// Per-leg result of a cancel-replace
+#[derive(Debug, Clone)]
+pub struct ReplaceResult {
+    /// The sid of the cancelled order, if the cancel leg succeeded
+    pub cancelled: Option<String>,
+    /// The output of the create leg, if the new order was accepted
+    pub created: Option<OrderCreateOutput>,
+    /// Why the cancel leg failed
+    pub cancel_error: Option<String>,
+    /// Whether the cancel leg failed with an unknown outcome, e.g. a timeout,
+    /// so the order may still have been cancelled
+    pub cancel_unknown: bool,
+    /// Why the create leg failed, or was not sent
+    pub create_error: Option<String>,
+    /// Sequence number of the last committed leg
+    pub seq: Option<u64>,
+    /// Version of the last committed leg
+    pub version: Option<u64>,
+}
+
+impl ReplaceResult {
+    /// Map the responses of a cancel intent and the create intent sent after
+    /// it to their legs.
+    ///
+    /// `create` is `None` when the create leg was not sent.
+    pub fn from_legs(
+        cancel_sid: &str,
+        cancel: Result<SendIntentResponse>,
+        create: Option<Result<SendIntentResponse>>,
+    ) -> Self {
+        let mut result = Self {
+            cancelled: None,
+            created: None,
+            cancel_error: None,
+            cancel_unknown: false,
+            create_error: None,
+            seq: None,
+            version: None,
+        };
+
+        match cancel {
+            Ok(response) => {
+                result.seq = Some(response.seq);
+                result.version = Some(response.version);
+                match response.output {
+                    IntentOutput::OrderCancel(output) => {
+                        result.cancelled = output
+                            .outputs
+                            .into_iter()
+                            .find(|c| c.sid == cancel_sid)
+                            .map(|c| c.sid);
+                        if result.cancelled.is_none() {
+                            result.cancel_error =
+                                Some(format!("Order {} was not cancelled", cancel_sid));
+                        }
+                    }
+                    other => {
+                        result.cancel_error = Some(format!(
+                            "Unexpected output for order cancel intent: {:?}",
+                            other
+                        ))
+                    }
+                }
+            }
+            Err(e) if e.is_retryable() => {
+                result.cancel_unknown = true;
+                result.cancel_error = Some(format!("Outcome unknown: {}", e));
+            }
+            Err(e) => result.cancel_error = Some(e.to_string()),
+        }
+
+        match create {
+            Some(Ok(response)) => {
+                result.seq = Some(response.seq);
+                result.version = Some(response.version);
+                match response.output {
+                    IntentOutput::OrderCreate(output) => {
+                        result.created = output.outputs.into_iter().next();
+                        if result.created.is_none() {
+                            result.create_error = Some("No output returned for order".to_string());
+                        }
+                    }
+                    other => {
+                        result.create_error = Some(format!(
+                            "Unexpected output for order create intent: {:?}",
+                            other
+                        ))
+                    }
+                }
+            }
+            Some(Err(e)) => result.create_error = Some(e.to_string()),
+            None if result.cancel_unknown => {
+                result.create_error =
+                    Some("Not sent: the outcome of the cancel leg is unknown".to_string())
+            }
+            None => result.create_error = Some("Not sent: the cancel leg failed".to_string()),
+        }
+
+        result
+    }
+
+    /// Map the response of a native [`ActionPayload::OrderReplace`] intent to
+    /// its cancel and create legs
+    pub fn from_response(cancel_sid: &str, response: SendIntentResponse) -> Result<Self> {
+        match response.output {
+            IntentOutput::OrderReplace(output) => {
+                let cancelled = output
+                    .cancels
+                    .into_iter()
+                    .find(|c| c.sid == cancel_sid)
+                    .map(|c| c.sid);
+                let created = output.orders.into_iter().next();
+                Ok(Self {
+                    cancel_error: cancelled
+                        .is_none()
+                        .then(|| format!("Order {} was not cancelled", cancel_sid)),
+                    cancel_unknown: false,
+                    create_error: created
+                        .is_none()
+                        .then(|| "No output returned for order".to_string()),
+                    cancelled,
+                    created,
+                    seq: Some(response.seq),
+                    version: Some(response.version),
+                })
+            }
+            other => Err(EkidenError::general(format!(
+                "Unexpected output for replace intent: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Check if both the cancel and the create leg succeeded
+    pub fn is_complete(&self) -> bool {
+        self.cancelled.is_some() && self.created.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        OrderCancelIntentOutput, OrderCancelOutput, OrderCreate, OrderCreateAction,
+        OrderCreateIntentOutput, OrderReplaceIntentOutput,
+    };
+
+    fn order(client_order_id: Option<&str>) -> OrderCreate {
+        OrderCreate {
+            side: "buy".to_string(),
+            size: 1,
+            price: 100,
+            leverage: 1,
+            r#type: "limit".to_string(),
+            market_addr: "0x1".to_string(),
+            is_cross: true,
+            time_in_force: None,
+            client_order_id: client_order_id.map(|s| s.to_string()),
+            trigger_price: None,
+            reduce_only: false,
+        }
+    }
+
+    fn created(sid: &str) -> OrderCreateOutput {
+        OrderCreateOutput {
+            sid: sid.to_string(),
+            client_order_id: None,
+        }
+    }
+
+    #[test]
+    fn test_generate_client_order_id() {
+        let a = generate_client_order_id();
+        let b = generate_client_order_id();
+        assert_eq!(a.len(), 32);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_record_intent() {
+        let tracker = ClientOrderTracker::new();
+        let payload = ActionPayload::OrderCreate(OrderCreateAction {
+            orders: vec![order(Some("a")), order(None), order(Some("c"))],
+        });
+        let output = IntentOutput::OrderCreate(OrderCreateIntentOutput {
+            outputs: vec![created("1"), created("2"), created("3")],
+        });
+
+        tracker.record_intent(&payload, &output);
+
+        assert_eq!(tracker.len(), 2);
+        assert_eq!(tracker.sid("a"), Some("1".to_string()));
+        assert_eq!(tracker.sid("c"), Some("3".to_string()));
+        assert_eq!(tracker.client_order_id("3"), Some("c".to_string()));
+        assert_eq!(tracker.client_order_id("2"), None);
+
+        assert_eq!(tracker.remove("a"), Some("1".to_string()));
+        assert_eq!(tracker.sid("a"), None);
+    }
+
+    #[test]
+    fn test_record_intent_mismatched_outputs() {
+        let payload = ActionPayload::OrderCreate(OrderCreateAction {
+            orders: vec![order(Some("a")), order(Some("b"))],
+        });
+
+        // One output short and no echoed ids: nothing can be matched safely
+        let tracker = ClientOrderTracker::new();
+        let output = IntentOutput::OrderCreate(OrderCreateIntentOutput {
+            outputs: vec![created("2")],
+        });
+        tracker.record_intent(&payload, &output);
+        assert!(tracker.is_empty());
+
+        // Echoed ids are used whatever the position
+        let output = IntentOutput::OrderCreate(OrderCreateIntentOutput {
+            outputs: vec![OrderCreateOutput {
+                sid: "2".to_string(),
+                client_order_id: Some("b".to_string()),
+            }],
+        });
+        tracker.record_intent(&payload, &output);
+        assert_eq!(tracker.len(), 1);
+        assert_eq!(tracker.sid("b"), Some("2".to_string()));
+    }
+
+    #[test]
+    fn test_replace_result() {
+        let response = SendIntentResponse {
+            output: IntentOutput::OrderReplace(OrderReplaceIntentOutput {
+                cancels: vec![OrderCancelOutput {
+                    sid: "old".to_string(),
+                }],
+                orders: vec![created("new")],
+            }),
+            seq: 1,
+            version: 2,
+            timestamp: 3,
+        };
+        let result = ReplaceResult::from_response("old", response).unwrap();
+        assert!(result.is_complete());
+        assert_eq!(result.cancelled.as_deref(), Some("old"));
+        assert_eq!(result.created.unwrap().sid, "new");
+
+        let response = SendIntentResponse {
+            output: IntentOutput::OrderReplace(OrderReplaceIntentOutput {
+                cancels: vec![],
+                orders: vec![created("new")],
+            }),
+            seq: 1,
+            version: 2,
+            timestamp: 3,
+        };
+        let result = ReplaceResult::from_response("old", response).unwrap();
+        assert!(!result.is_complete());
+        assert!(result.cancelled.is_none());
+        assert!(result.cancel_error.is_some());
+    }
+
+    #[test]
+    fn test_replace_result_from_legs() {
+        let cancelled = SendIntentResponse {
+            output: IntentOutput::OrderCancel(OrderCancelIntentOutput {
+                outputs: vec![OrderCancelOutput {
+                    sid: "old".to_string(),
+                }],
+            }),
+            seq: 1,
+            version: 2,
+            timestamp: 3,
+        };
+        let created_response = SendIntentResponse {
+            output: IntentOutput::OrderCreate(OrderCreateIntentOutput {
+                outputs: vec![created("new")],
+            }),
+            seq: 2,
+            version: 4,
+            timestamp: 5,
+        };
+        let result = ReplaceResult::from_legs("old", Ok(cancelled), Some(Ok(created_response)));
+        assert!(result.is_complete());
+        assert_eq!(result.created.unwrap().sid, "new");
+        assert_eq!(result.seq, Some(2));
+        assert_eq!(result.version, Some(4));
+
+        // The create leg is not sent when the cancel fails
+        let result = ReplaceResult::from_legs(
+            "old",
+            Err(EkidenError::api(400, "unknown order".to_string())),
+            None,
+        );
+        assert!(!result.is_complete());
+        assert!(result.cancel_error.unwrap().contains("unknown order"));
+        assert!(!result.cancel_unknown);
+        assert!(result.create_error.is_some());
+        assert_eq!(result.seq, None);
+
+        // A timed out cancel may still have gone through
+        let result = ReplaceResult::from_legs("old", Err(EkidenError::Timeout), None);
+        assert!(result.cancel_unknown);
+        assert!(result.cancel_error.unwrap().starts_with("Outcome unknown"));
+    }
+
+    #[test]
+    fn test_order_builder() {
+        let order = OrderBuilder::stop_limit("0x1", OrderSide::Sell, 5, 90, 95)
+            .leverage(10)
+            .isolated()
+            .reduce_only(true)
+            .client_order_id("sl-1")
+            .build()
+            .unwrap();
+        assert_eq!(order.r#type, "stop_limit");
+        assert_eq!(order.side, "sell");
+        assert_eq!(order.trigger_price, Some(95));
+        assert!(order.reduce_only);
+        assert!(!order.is_cross);
+        assert_eq!(order.client_order_id.as_deref(), Some("sl-1"));
+
+        let order = OrderBuilder::market("0x1", OrderSide::Buy, 1)
+            .build()
+            .unwrap();
+        assert_eq!(order.r#type, "market");
+        assert_eq!(order.trigger_price, None);
+    }
+
+    #[test]
+    fn test_order_builder_validation() {
+        assert!(OrderBuilder::limit("0x1", OrderSide::Buy, 0, 100)
+            .build()
+            .is_err());
+        assert!(OrderBuilder::limit("0x1", OrderSide::Buy, 1, 0)
+            .build()
+            .is_err());
+        assert!(OrderBuilder::market("0x1", OrderSide::Buy, 1)
+            .order_type(OrderType::StopMarket)
+            .build()
+            .is_err());
+        assert!(OrderBuilder::limit("0x1", OrderSide::Buy, 1, 100)
+            .trigger_price(90)
+            .build()
+            .is_err());
+    }
+}
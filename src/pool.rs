@@ -0,0 +1,341 @@
+use crate::client::EkidenClient;
+use crate::config::EkidenConfig;
+use crate::error::{EkidenError, Result};
+use crate::mnemonic::AccountKeys;
+use crate::rate_limit::RateLimit;
+use crate::signer::Signer;
+use crate::types::{PortfolioPosition, PortfolioResponse, PortfolioSummary, PortfolioVault};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::warn;
+
+/// Many Ekiden accounts served from one process.
+///
+/// Every account gets its own root, funding and trading credentials, nonce
+/// state and rate limit, while all of them share one HTTP connection pool
+/// and one WebSocket connection. The rate limit is enforced by the account's
+/// client itself, so it also applies to the clients handed out by the pool.
+#[derive(Debug, Clone)]
+pub struct AccountPool {
+    transport: EkidenClient,
+    rate_limit: Option<RateLimit>,
+    accounts: Arc<RwLock<HashMap<String, EkidenClient>>>,
+}
+
+impl AccountPool {
+    /// Create an empty pool with the given configuration
+    pub fn new(config: EkidenConfig) -> Result<Self> {
+        Ok(Self::from_client(EkidenClient::new(config)?))
+    }
+
+    /// Create an empty pool sharing the transport of an existing client
+    pub fn from_client(client: EkidenClient) -> Self {
+        Self {
+            transport: client,
+            rate_limit: None,
+            accounts: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Set the default rate limit for accounts added afterwards
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Get the client used for public endpoints and WebSocket streams
+    pub fn public_client(&self) -> &EkidenClient {
+        &self.transport
+    }
+
+    /// Add an account with its root, funding and trading signers.
+    ///
+    /// Replaces any account with the same id.
+    pub async fn add_account<S: Into<String>>(
+        &self,
+        id: S,
+        root: Arc<dyn Signer>,
+        funding: Arc<dyn Signer>,
+        trading: Arc<dyn Signer>,
+    ) -> EkidenClient {
+        let client = self.transport.with_shared_transport();
+        client.set_signer(root).await;
+        client.set_funding_signer(funding).await;
+        client.set_trading_signer(trading).await;
+        client.set_rate_limit(self.rate_limit);
+
+        self.accounts
+            .write()
+            .await
+            .insert(id.into(), client.clone());
+        client
+    }
+
+    /// Add an account from its derived or loaded keys
+    pub async fn add_account_keys<S: Into<String>>(
+        &self,
+        id: S,
+        keys: AccountKeys,
+    ) -> EkidenClient {
+        self.add_account(
+            id,
+            Arc::new(keys.root),
+            Arc::new(keys.funding),
+            Arc::new(keys.trading),
+        )
+        .await
+    }
+
+    /// Remove an account, returning its client
+    pub async fn remove_account(&self, id: &str) -> Option<EkidenClient> {
+        self.accounts.write().await.remove(id)
+    }
+
+    /// Set or clear the rate limit of a single account
+    pub async fn set_rate_limit(&self, id: &str, rate_limit: Option<RateLimit>) -> Result<()> {
+        self.client(id).await?.set_rate_limit(rate_limit);
+        Ok(())
+    }
+
+    /// Get the ids of all accounts, sorted
+    pub async fn account_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.accounts.read().await.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Get the number of accounts
+    pub async fn len(&self) -> usize {
+        self.accounts.read().await.len()
+    }
+
+    /// Check if the pool has no accounts
+    pub async fn is_empty(&self) -> bool {
+        self.accounts.read().await.is_empty()
+    }
+
+    /// Get an account's client; its requests are rate limited
+    pub async fn client(&self, id: &str) -> Result<EkidenClient> {
+        self.accounts
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| unknown_account(id))
+    }
+
+    /// Run a call against an account's client
+    pub async fn call<F, Fut, T>(&self, id: &str, f: F) -> Result<T>
+    where
+        F: FnOnce(EkidenClient) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        f(self.client(id).await?).await
+    }
+
+    /// Authorize the root, funding and trading keys of one account
+    pub async fn authorize(&self, id: &str) -> Result<()> {
+        self.call(id, |client| async move {
+            client.authorize().await?;
+            client.authorize_funding().await?;
+            client.authorize_trading().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Authorize every account concurrently, returning the failures by id
+    pub async fn authorize_all(&self) -> BTreeMap<String, EkidenError> {
+        let ids = self.account_ids().await;
+        let results = futures_util::future::join_all(ids.iter().map(|id| self.authorize(id))).await;
+        ids.into_iter()
+            .zip(results)
+            .filter_map(|(id, result)| result.err().map(|e| (id, e)))
+            .collect()
+    }
+
+    /// Fetch the portfolio of every account and aggregate them
+    pub async fn portfolio(&self) -> AggregatedPortfolio {
+        let ids = self.account_ids().await;
+        let results = futures_util::future::join_all(ids.iter().map(|id| {
+            self.call(
+                id,
+                |client| async move { client.get_user_portfolio().await },
+            )
+        }))
+        .await;
+
+        let mut aggregated = AggregatedPortfolio::default();
+        for (id, result) in ids.into_iter().zip(results) {
+            match result {
+                Ok(portfolio) => aggregated.add(id, portfolio),
+                Err(e) => {
+                    warn!("Failed to fetch portfolio of account {}: {}", id, e);
+                    aggregated.errors.insert(id, e);
+                }
+            }
+        }
+        aggregated
+    }
+}
+
+/// Portfolios of all accounts in a pool, with combined totals
+#[derive(Debug, Default)]
+pub struct AggregatedPortfolio {
+    /// Portfolio of each account that could be fetched
+    pub accounts: BTreeMap<String, PortfolioResponse>,
+    /// Accounts whose portfolio could not be fetched
+    pub errors: BTreeMap<String, EkidenError>,
+}
+
+impl AggregatedPortfolio {
+    fn add(&mut self, id: String, portfolio: PortfolioResponse) {
+        self.accounts.insert(id, portfolio);
+    }
+
+    /// Sum of the account summaries; a field is `None` if no account reports it
+    pub fn summary(&self) -> PortfolioSummary {
+        let summaries: Vec<&PortfolioSummary> =
+            self.accounts.values().map(|p| &p.summary).collect();
+        PortfolioSummary {
+            total_value: sum(summaries.iter().map(|s| s.total_value)),
+            available_balance: sum(summaries.iter().map(|s| s.available_balance)),
+            locked_balance: sum(summaries.iter().map(|s| s.locked_balance)),
+            unrealized_pnl: sum(summaries.iter().map(|s| s.unrealized_pnl)),
+            margin_used: sum(summaries.iter().map(|s| s.margin_used)),
+            margin_available: sum(summaries.iter().map(|s| s.margin_available)),
+        }
+    }
+
+    /// All positions, tagged with their account id
+    pub fn positions(&self) -> Vec<(&str, &PortfolioPosition)> {
+        self.accounts
+            .iter()
+            .flat_map(|(id, p)| p.positions.iter().map(move |pos| (id.as_str(), pos)))
+            .collect()
+    }
+
+    /// All vault balances, tagged with their account id
+    pub fn vault_balances(&self) -> Vec<(&str, &PortfolioVault)> {
+        self.accounts
+            .iter()
+            .flat_map(|(id, p)| p.vault_balances.iter().map(move |v| (id.as_str(), v)))
+            .collect()
+    }
+
+    /// Total vault balance per asset across all accounts
+    pub fn balances_by_asset(&self) -> BTreeMap<String, u64> {
+        let mut balances = BTreeMap::new();
+        for (_, vault) in self.vault_balances() {
+            *balances.entry(vault.asset_addr.clone()).or_insert(0) += vault.balance;
+        }
+        balances
+    }
+}
+
+fn sum<T: std::iter::Sum<T>>(values: impl Iterator<Item = Option<T>>) -> Option<T> {
+    let values: Vec<T> = values.flatten().collect();
+    (!values.is_empty()).then(|| values.into_iter().sum())
+}
+
+fn unknown_account(id: &str) -> EkidenError {
+    EkidenError::config(format!("Unknown account {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::KeyPair;
+    use std::time::Duration;
+
+    fn keys() -> AccountKeys {
+        AccountKeys {
+            root: KeyPair::generate(),
+            funding: KeyPair::generate(),
+            trading: KeyPair::generate(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_accounts_are_isolated() {
+        let pool = AccountPool::new(EkidenConfig::default()).unwrap();
+        let alice_keys = keys();
+        let alice_trading = alice_keys.trading.public_key();
+        pool.add_account_keys("alice", alice_keys).await;
+        pool.add_account_keys("bob", keys()).await;
+
+        assert_eq!(pool.account_ids().await, vec!["alice", "bob"]);
+        let alice = pool.client("alice").await.unwrap();
+        let bob = pool.client("bob").await.unwrap();
+        assert_eq!(
+            alice.trading_signer().await.unwrap().public_key_hex(),
+            alice_trading
+        );
+        assert_ne!(
+            bob.trading_signer().await.unwrap().public_key_hex(),
+            alice_trading
+        );
+
+        assert!(pool.client("carol").await.is_err());
+        assert!(pool.remove_account("bob").await.is_some());
+        assert_eq!(pool.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_clients_are_rate_limited() {
+        // Nothing listens on port 1, so requests fail fast
+        let pool = AccountPool::new(EkidenConfig::new("http://127.0.0.1:1/api/v1").unwrap())
+            .unwrap()
+            .with_rate_limit(RateLimit::new(1, Duration::from_millis(50)));
+        let client = pool.add_account_keys("alice", keys()).await;
+        assert_eq!(
+            pool.client("alice").await.unwrap().rate_limit(),
+            Some(RateLimit::new(1, Duration::from_millis(50)))
+        );
+
+        // The client handed out by the pool is limited by itself
+        let start = tokio::time::Instant::now();
+        for _ in 0..2 {
+            let _ = client.get_markets(Default::default()).await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(40));
+
+        pool.set_rate_limit("alice", None).await.unwrap();
+        assert_eq!(client.rate_limit(), None);
+
+        let unknown = pool.call("bob", |_| async { Ok(()) }).await;
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn test_aggregated_summary() {
+        let portfolio = |total_value, pnl, balance| PortfolioResponse {
+            summary: PortfolioSummary {
+                total_value: Some(total_value),
+                available_balance: None,
+                locked_balance: None,
+                unrealized_pnl: Some(pnl),
+                margin_used: None,
+                margin_available: None,
+            },
+            positions: vec![],
+            vault_balances: vec![PortfolioVault {
+                id: 1,
+                asset_addr: "0xusdc".to_string(),
+                balance,
+            }],
+        };
+        let mut aggregated = AggregatedPortfolio::default();
+        aggregated.add("alice".to_string(), portfolio(100, -5, 40));
+        aggregated.add("bob".to_string(), portfolio(50, 10, 2));
+
+        let summary = aggregated.summary();
+        assert_eq!(summary.total_value, Some(150));
+        assert_eq!(summary.unrealized_pnl, Some(5));
+        assert_eq!(summary.available_balance, None);
+        assert_eq!(aggregated.balances_by_asset()["0xusdc"], 42);
+        assert_eq!(aggregated.vault_balances().len(), 2);
+    }
+}
@@ -0,0 +1,112 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Maximum number of requests allowed per period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    /// Create a new rate limit
+    pub fn new(requests: u32, per: Duration) -> Self {
+        Self {
+            requests: requests.max(1),
+            per,
+        }
+    }
+
+    /// Limit to the given number of requests per second
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket rate limiter.
+///
+/// Allows bursts of up to `requests` calls, refilling continuously over `per`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    /// Create a new limiter with a full bucket
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            bucket: Mutex::new(Bucket {
+                tokens: limit.requests as f64,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Get the configured limit
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Take a token if one is available, without waiting
+    pub fn try_acquire(&self) -> bool {
+        self.take().is_none()
+    }
+
+    /// Wait until a token is available and take it
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.take() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take a token, or return how long until the next one is available
+    fn take(&self) -> Option<Duration> {
+        let capacity = self.limit.requests as f64;
+        let rate = capacity / self.limit.per.as_secs_f64().max(f64::EPSILON);
+
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_limited() {
+        let limiter = RateLimiter::new(RateLimit::new(3, Duration::from_secs(60)));
+        assert!(limiter.try_acquire());
+        assert!(limiter.try_acquire());
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_refill() {
+        let limiter = RateLimiter::new(RateLimit::new(1, Duration::from_millis(50)));
+        limiter.acquire().await;
+        let start = Instant::now();
+        limiter.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+}
@@ -0,0 +1,59 @@
+use std::fmt::{Debug, Formatter};
+use zeroize::Zeroizing;
+
+/// Placeholder printed in place of secrets in `Debug` output
+pub const REDACTED: &str = "[REDACTED]";
+
+/// A string secret, such as a private key or bearer token.
+///
+/// The contents are zeroized on drop and never printed by `Debug`.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    /// Wrap a secret string
+    pub fn new<S: Into<String>>(secret: S) -> Self {
+        Self(Zeroizing::new(secret.into()))
+    }
+
+    /// Get the secret value
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    /// Check if the secret is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl Debug for SecretString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_string_redacted() {
+        let secret = SecretString::new("0xdeadbeef");
+        assert_eq!(secret.expose_secret(), "0xdeadbeef");
+        assert_eq!(format!("{:?}", secret), REDACTED);
+        assert_eq!(format!("{:?}", Some(secret)), "Some([REDACTED])");
+    }
+}
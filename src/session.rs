@@ -0,0 +1,305 @@
+use crate::error::{EkidenError, Result};
+use crate::keystore::{write_private, KeyRole};
+use crate::secret::SecretString;
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+const SESSION_VERSION: u32 = 1;
+
+/// Tokens expiring sooner than this are not restored
+pub const SESSION_MIN_TTL: Duration = Duration::from_secs(60);
+
+/// Authentication token of one role, with its expiry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionToken {
+    #[serde(serialize_with = "expose", deserialize_with = "conceal")]
+    pub token: SecretString,
+    /// Expiry in seconds since the Unix epoch, read from the JWT `exp` claim
+    pub expires_at: Option<u64>,
+    /// Public key the token was issued for
+    pub public_key: Option<String>,
+}
+
+impl SessionToken {
+    /// Wrap a token, reading its expiry from the JWT payload
+    pub fn new<S: Into<String>>(token: S, public_key: Option<String>) -> Self {
+        let token = SecretString::new(token);
+        Self {
+            expires_at: token_expiry(token.expose_secret()),
+            token,
+            public_key,
+        }
+    }
+
+    /// Check if the token's `exp` claim is at least `min_ttl` away on the
+    /// local clock.
+    ///
+    /// This is an expiry check only: a token revoked by the gateway still
+    /// passes. Tokens without a readable expiry never pass.
+    pub fn is_unexpired(&self, min_ttl: Duration) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.expires_at
+            .is_some_and(|expires_at| expires_at > now + min_ttl.as_secs())
+    }
+}
+
+/// Tokens and nonce state of a client, as saved between restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    /// Seconds since the Unix epoch when the session was exported
+    pub saved_at: u64,
+    /// Highest intent nonce issued or observed
+    pub nonce_high_water: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<SessionToken>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funding: Option<SessionToken>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trading: Option<SessionToken>,
+}
+
+impl Session {
+    /// Create an empty session
+    pub fn new(nonce_high_water: u64) -> Self {
+        Self {
+            version: SESSION_VERSION,
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            nonce_high_water,
+            root: None,
+            funding: None,
+            trading: None,
+        }
+    }
+
+    /// Get the token of a role
+    pub fn token(&self, role: KeyRole) -> Option<&SessionToken> {
+        match role {
+            KeyRole::Root => self.root.as_ref(),
+            KeyRole::Funding => self.funding.as_ref(),
+            KeyRole::Trading => self.trading.as_ref(),
+        }
+    }
+
+    /// Set the token of a role
+    pub fn set_token(&mut self, role: KeyRole, token: SessionToken) {
+        let slot = match role {
+            KeyRole::Root => &mut self.root,
+            KeyRole::Funding => &mut self.funding,
+            KeyRole::Trading => &mut self.trading,
+        };
+        *slot = Some(token);
+    }
+}
+
+/// Storage for a client session, e.g. a file, a database row or a secret manager
+#[async_trait]
+pub trait SessionStore: Debug + Send + Sync {
+    /// Load the saved session, if any
+    async fn load(&self) -> Result<Option<Session>>;
+
+    /// Save the session, replacing any previous one
+    async fn save(&self, session: &Session) -> Result<()>;
+
+    /// Delete the saved session
+    async fn clear(&self) -> Result<()>;
+}
+
+/// Session stored as a JSON file readable by the owner only.
+///
+/// Saves go through a new temporary file created with mode 0600, so tokens
+/// are never readable by other users, even while being written.
+#[derive(Debug, Clone)]
+pub struct FileSessionStore {
+    path: PathBuf,
+}
+
+impl FileSessionStore {
+    /// Create a store at the given file path
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Get the session file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl SessionStore for FileSessionStore {
+    async fn load(&self) -> Result<Option<Session>> {
+        let contents = match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let session: Session = serde_json::from_str(&contents)?;
+        if session.version != SESSION_VERSION {
+            return Err(EkidenError::config(format!(
+                "Unsupported session version {}",
+                session.version
+            )));
+        }
+        Ok(Some(session))
+    }
+
+    async fn save(&self, session: &Session) -> Result<()> {
+        let path = self.path.clone();
+        let contents = serde_json::to_vec_pretty(session)?;
+        tokio::task::spawn_blocking(move || write_private(&path, &contents))
+            .await
+            .map_err(|e| EkidenError::general(format!("Failed to save session: {}", e)))?
+    }
+
+    async fn clear(&self) -> Result<()> {
+        match tokio::fs::remove_file(&self.path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Session kept in memory, mainly for tests
+#[derive(Debug, Default)]
+pub struct MemorySessionStore {
+    session: Mutex<Option<Session>>,
+}
+
+impl MemorySessionStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemorySessionStore {
+    async fn load(&self) -> Result<Option<Session>> {
+        Ok(self.session.lock().await.clone())
+    }
+
+    async fn save(&self, session: &Session) -> Result<()> {
+        *self.session.lock().await = Some(session.clone());
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<()> {
+        *self.session.lock().await = None;
+        Ok(())
+    }
+}
+
+/// Read the `exp` claim of a JWT without verifying it
+pub fn token_expiry(token: &str) -> Option<u64> {
+    let payload = token.split('.').nth(1)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&payload).ok()?;
+    claims.get("exp")?.as_u64()
+}
+
+fn expose<S: Serializer>(
+    secret: &SecretString,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(secret.expose_secret())
+}
+
+fn conceal<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<SecretString, D::Error> {
+    String::deserialize(deserializer).map(SecretString::from)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build an unsigned JWT expiring `ttl` seconds from now
+    pub(crate) fn jwt(ttl: i64) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let claims = serde_json::json!({ "sub": "0x1", "exp": now + ttl });
+        format!(
+            "{}.{}.sig",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        )
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        assert!(SessionToken::new(jwt(3600), None).is_unexpired(SESSION_MIN_TTL));
+        assert!(!SessionToken::new(jwt(30), None).is_unexpired(SESSION_MIN_TTL));
+        assert!(!SessionToken::new(jwt(-10), None).is_unexpired(Duration::ZERO));
+
+        let opaque = SessionToken::new("opaque-token", None);
+        assert_eq!(opaque.expires_at, None);
+        assert!(!opaque.is_unexpired(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_file_store_roundtrip() {
+        let path =
+            std::env::temp_dir().join(format!("ekiden-session-{}.json", uuid::Uuid::new_v4()));
+        let store = FileSessionStore::new(&path);
+        assert!(store.load().await.unwrap().is_none());
+
+        // A link planted at a predictable temp path must not receive tokens
+        #[cfg(unix)]
+        let decoy = {
+            let decoy = path.with_extension("decoy");
+            std::fs::write(&decoy, b"").unwrap();
+            let mut tmp = path.clone().into_os_string();
+            tmp.push(".tmp");
+            std::os::unix::fs::symlink(&decoy, &tmp).unwrap();
+            (decoy, PathBuf::from(tmp))
+        };
+
+        let token = jwt(3600);
+        let mut session = Session::new(42);
+        session.set_token(KeyRole::Trading, SessionToken::new(token.clone(), None));
+        store.save(&session).await.unwrap();
+        assert!(!format!("{:?}", session).contains(&token));
+
+        // Saving again replaces the file without widening its permissions
+        store.save(&session).await.unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+
+            let (decoy, tmp) = decoy;
+            assert!(std::fs::read(&decoy).unwrap().is_empty());
+            std::fs::remove_file(decoy).unwrap();
+            std::fs::remove_file(tmp).unwrap();
+        }
+
+        let loaded = store.load().await.unwrap().unwrap();
+        assert_eq!(loaded.nonce_high_water, 42);
+        assert!(loaded.token(KeyRole::Root).is_none());
+        let trading = loaded.token(KeyRole::Trading).unwrap();
+        assert_eq!(trading.token.expose_secret(), token);
+        assert_eq!(trading.expires_at, token_expiry(&token));
+
+        store.clear().await.unwrap();
+        assert!(store.load().await.unwrap().is_none());
+        store.clear().await.unwrap();
+    }
+}
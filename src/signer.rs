@@ -0,0 +1,459 @@
+use crate::error::{EkidenError, Result};
+use crate::types::IntentSignatureBody;
+use crate::utils::KeyPair;
+use aptos_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    signing_message, HashValue, Signature, SigningKey, ValidCryptoMaterialStringExt,
+};
+use aptos_rust_sdk_types::api_types::{address::AccountAddress, transaction::RawTransaction};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::time::Duration;
+use url::Url;
+
+/// Source of Ed25519 signatures for authorization, intents and transactions.
+///
+/// Implementations may hold the key in memory or delegate to a signing
+/// service, so the trading process never has to see the raw key.
+#[async_trait]
+pub trait Signer: Debug + Send + Sync {
+    /// Get the public key of the signer
+    fn public_key(&self) -> Ed25519PublicKey;
+
+    /// Sign raw message bytes
+    async fn sign_message(&self, message: &[u8]) -> Result<Ed25519Signature>;
+
+    /// Sign the "AUTHORIZE" message for the /authorize endpoint
+    async fn sign_authorize(&self, timestamp: i64, nonce: &str) -> Result<Ed25519Signature> {
+        self.sign_message(&authorize_message(timestamp, nonce))
+            .await
+    }
+
+    /// Sign an intent body
+    async fn sign_intent(&self, intent: &IntentSignatureBody) -> Result<Ed25519Signature> {
+        self.sign_message(&intent_message(intent)?).await
+    }
+
+    /// Sign an Aptos raw transaction
+    async fn sign_transaction(&self, raw_txn: &RawTransaction) -> Result<Ed25519Signature> {
+        self.sign_message(&transaction_message(raw_txn)?).await
+    }
+
+    /// Sign an Aptos raw transaction whose gas is paid by `fee_payer`.
+    ///
+    /// The sender and the fee payer sign the same message.
+    async fn sign_fee_payer_transaction(
+        &self,
+        raw_txn: &RawTransaction,
+        fee_payer: AccountAddress,
+    ) -> Result<Ed25519Signature> {
+        self.sign_message(&fee_payer_transaction_message(raw_txn, fee_payer)?)
+            .await
+    }
+
+    /// Get the public key as hex string
+    fn public_key_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.public_key().to_bytes()))
+    }
+}
+
+#[async_trait]
+impl Signer for KeyPair {
+    fn public_key(&self) -> Ed25519PublicKey {
+        self.get_public_key()
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Ed25519Signature> {
+        Ok(self.get_private_key().sign_arbitrary_message(message))
+    }
+}
+
+/// Message signed for the /authorize endpoint
+pub fn authorize_message(timestamp: i64, nonce: &str) -> Vec<u8> {
+    format!("AUTHORIZE|{}|{}", timestamp, nonce).into_bytes()
+}
+
+/// Message signed for an intent
+pub fn intent_message(intent: &IntentSignatureBody) -> Result<Vec<u8>> {
+    signing_message(intent).map_err(|e| EkidenError::crypto(format!("Invalid intent: {}", e)))
+}
+
+/// Message signed for an Aptos raw transaction
+pub fn transaction_message(raw_txn: &RawTransaction) -> Result<Vec<u8>> {
+    let hash = HashValue::sha3_256_of("APTOS::RawTransaction".as_bytes());
+    let bytes = bcs::to_bytes(raw_txn)
+        .map_err(|e| EkidenError::crypto(format!("Invalid transaction: {}", e)))?;
+
+    let mut message = hash.to_vec();
+    message.extend(bytes);
+    Ok(message)
+}
+
+/// Raw transaction with its additional signers, as signed by multi-agent and
+/// fee-payer transactions
+#[derive(Serialize)]
+enum RawTransactionWithData<'a> {
+    // Unused, but its position fixes the BCS tag of the fee-payer variant
+    #[allow(dead_code)]
+    MultiAgent {
+        raw_txn: &'a RawTransaction,
+        secondary_signer_addresses: Vec<AccountAddress>,
+    },
+    MultiAgentWithFeePayer {
+        raw_txn: &'a RawTransaction,
+        secondary_signer_addresses: Vec<AccountAddress>,
+        fee_payer_address: AccountAddress,
+    },
+}
+
+/// Message signed by the sender and the fee payer of a sponsored transaction
+pub fn fee_payer_transaction_message(
+    raw_txn: &RawTransaction,
+    fee_payer: AccountAddress,
+) -> Result<Vec<u8>> {
+    let hash = HashValue::sha3_256_of("APTOS::RawTransactionWithData".as_bytes());
+    let bytes = bcs::to_bytes(&RawTransactionWithData::MultiAgentWithFeePayer {
+        raw_txn,
+        secondary_signer_addresses: vec![],
+        fee_payer_address: fee_payer,
+    })
+    .map_err(|e| EkidenError::crypto(format!("Invalid transaction: {}", e)))?;
+
+    let mut message = hash.to_vec();
+    message.extend(bytes);
+    Ok(message)
+}
+
+/// Encode a signature as hex string
+pub fn signature_hex(signature: &Ed25519Signature) -> String {
+    format!("0x{}", hex::encode(signature.to_bytes()))
+}
+
+/// Kind of message sent to a signing service, for its signing policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignKind {
+    Message,
+    Authorize,
+    Intent,
+    Transaction,
+}
+
+/// Location of a signing service
+#[derive(Debug, Clone)]
+pub enum SignerEndpoint {
+    /// JSON over HTTP POST
+    Http(Url),
+    /// Newline-delimited JSON over a Unix socket
+    #[cfg(unix)]
+    Unix(std::path::PathBuf),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum SignerRequest<'a> {
+    PublicKey {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key_id: Option<&'a str>,
+    },
+    Sign {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key_id: Option<&'a str>,
+        kind: SignKind,
+        message: String,
+    },
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SignerResponse {
+    #[serde(default)]
+    public_key: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Signer delegating to a local signing service over HTTP or a Unix socket.
+///
+/// Requests are JSON objects tagged by `method` (`public_key` or `sign`,
+/// with a hex `message` and its `kind`); responses carry `public_key`,
+/// `signature` or `error`. Returned signatures are verified against the
+/// service's public key before use.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    endpoint: SignerEndpoint,
+    key_id: Option<String>,
+    public_key: Ed25519PublicKey,
+    http: reqwest::Client,
+}
+
+impl RemoteSigner {
+    /// Connect to a signing service and fetch its public key
+    pub async fn connect(endpoint: SignerEndpoint, key_id: Option<String>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        let response = Self::call(
+            &endpoint,
+            &http,
+            &SignerRequest::PublicKey {
+                key_id: key_id.as_deref(),
+            },
+        )
+        .await?;
+
+        let public_key = response
+            .public_key
+            .ok_or_else(|| EkidenError::auth("Signing service returned no public key"))?;
+        let public_key = Ed25519PublicKey::from_encoded_string(&public_key)
+            .map_err(|e| EkidenError::crypto(format!("Invalid public key from signer: {}", e)))?;
+
+        Ok(Self {
+            endpoint,
+            key_id,
+            public_key,
+            http,
+        })
+    }
+
+    /// Connect to a signing service over HTTP
+    pub async fn http(url: &str, key_id: Option<String>) -> Result<Self> {
+        Self::connect(SignerEndpoint::Http(Url::parse(url)?), key_id).await
+    }
+
+    /// Connect to a signing service over a Unix socket
+    #[cfg(unix)]
+    pub async fn unix<P: Into<std::path::PathBuf>>(
+        path: P,
+        key_id: Option<String>,
+    ) -> Result<Self> {
+        Self::connect(SignerEndpoint::Unix(path.into()), key_id).await
+    }
+
+    /// Get the signing service endpoint
+    pub fn endpoint(&self) -> &SignerEndpoint {
+        &self.endpoint
+    }
+
+    async fn sign_kind(&self, kind: SignKind, message: &[u8]) -> Result<Ed25519Signature> {
+        let response = Self::call(
+            &self.endpoint,
+            &self.http,
+            &SignerRequest::Sign {
+                key_id: self.key_id.as_deref(),
+                kind,
+                message: format!("0x{}", hex::encode(message)),
+            },
+        )
+        .await?;
+
+        let signature = response
+            .signature
+            .ok_or_else(|| EkidenError::auth("Signing service returned no signature"))?;
+        let signature = Ed25519Signature::from_encoded_string(&signature)
+            .map_err(|e| EkidenError::crypto(format!("Invalid signature from signer: {}", e)))?;
+
+        signature
+            .verify_arbitrary_msg(message, &self.public_key)
+            .map_err(|_| EkidenError::crypto("Signature from signer does not verify"))?;
+        Ok(signature)
+    }
+
+    async fn call(
+        endpoint: &SignerEndpoint,
+        http: &reqwest::Client,
+        request: &SignerRequest<'_>,
+    ) -> Result<SignerResponse> {
+        let response: SignerResponse = match endpoint {
+            SignerEndpoint::Http(url) => {
+                let response = http.post(url.clone()).json(request).send().await?;
+                let status = response.status();
+                if !status.is_success() {
+                    let message = response.text().await.unwrap_or_default();
+                    return Err(EkidenError::api(status.as_u16(), message));
+                }
+                response.json().await?
+            }
+            #[cfg(unix)]
+            SignerEndpoint::Unix(path) => {
+                use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+                let stream = tokio::net::UnixStream::connect(path).await?;
+                let (reader, mut writer) = stream.into_split();
+                let mut line = serde_json::to_vec(request)?;
+                line.push(b'\n');
+                writer.write_all(&line).await?;
+
+                let mut response = String::new();
+                BufReader::new(reader).read_line(&mut response).await?;
+                if response.is_empty() {
+                    return Err(EkidenError::ConnectionClosed);
+                }
+                serde_json::from_str(&response)?
+            }
+        };
+
+        match response.error {
+            Some(error) => Err(EkidenError::auth(format!(
+                "Signing service error: {}",
+                error
+            ))),
+            None => Ok(response),
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    fn public_key(&self) -> Ed25519PublicKey {
+        self.public_key.clone()
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Ed25519Signature> {
+        self.sign_kind(SignKind::Message, message).await
+    }
+
+    async fn sign_authorize(&self, timestamp: i64, nonce: &str) -> Result<Ed25519Signature> {
+        self.sign_kind(SignKind::Authorize, &authorize_message(timestamp, nonce))
+            .await
+    }
+
+    async fn sign_intent(&self, intent: &IntentSignatureBody) -> Result<Ed25519Signature> {
+        self.sign_kind(SignKind::Intent, &intent_message(intent)?)
+            .await
+    }
+
+    async fn sign_transaction(&self, raw_txn: &RawTransaction) -> Result<Ed25519Signature> {
+        self.sign_kind(SignKind::Transaction, &transaction_message(raw_txn)?)
+            .await
+    }
+
+    async fn sign_fee_payer_transaction(
+        &self,
+        raw_txn: &RawTransaction,
+        fee_payer: AccountAddress,
+    ) -> Result<Ed25519Signature> {
+        self.sign_kind(
+            SignKind::Transaction,
+            &fee_payer_transaction_message(raw_txn, fee_payer)?,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ActionPayload, OrderCancelAllAction};
+    use crate::utils::Crypto;
+
+    fn intent() -> IntentSignatureBody {
+        IntentSignatureBody {
+            payload: ActionPayload::OrderCancelAll(OrderCancelAllAction { market_addr: None }),
+            nonce: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_key_pair_signer() {
+        let key_pair = KeyPair::generate();
+        let signer: &dyn Signer = &key_pair;
+        assert_eq!(signer.public_key_hex(), key_pair.public_key());
+
+        let signature = signer.sign_intent(&intent()).await.unwrap();
+        let message = intent_message(&intent()).unwrap();
+        assert!(Crypto::verify_signature(
+            &message,
+            &signature_hex(&signature),
+            &key_pair.public_key()
+        )
+        .unwrap());
+
+        let signature = signer.sign_authorize(1, "nonce").await.unwrap();
+        assert_eq!(
+            signature_hex(&signature),
+            key_pair.sign_authorize(1, "nonce")
+        );
+    }
+
+    #[test]
+    fn test_fee_payer_transaction_message() {
+        use crate::aptos::entry;
+        use aptos_rust_sdk_types::api_types::chain_id::ChainId;
+
+        let sender = AccountAddress::new([1u8; 32]);
+        let fee_payer = AccountAddress::new([2u8; 32]);
+        let payload = entry::vault::WITHDRAW_FROM_USER
+            .payload(sender, vec![sender.into(), 1u64.into()])
+            .unwrap();
+        let raw_txn = RawTransaction::new(sender, 0, payload, 1000, 100, 0, ChainId::Testnet);
+
+        let message = fee_payer_transaction_message(&raw_txn, fee_payer).unwrap();
+        let txn_bytes = bcs::to_bytes(&raw_txn).unwrap();
+        assert_eq!(
+            &message[..32],
+            HashValue::sha3_256_of(b"APTOS::RawTransactionWithData").as_ref()
+        );
+        // Fee-payer variant, the transaction, no secondary signers, fee payer
+        assert_eq!(message[32], 1);
+        assert_eq!(&message[33..33 + txn_bytes.len()], &txn_bytes[..]);
+        assert_eq!(message[33 + txn_bytes.len()], 0);
+        assert_eq!(&message[message.len() - 32..], &fee_payer.to_bytes()[..]);
+    }
+
+    /// Serve one signing service connection per key, signing with the given key pair
+    #[cfg(unix)]
+    fn serve(path: std::path::PathBuf, public: KeyPair, signing: KeyPair) {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::UnixListener::bind(path).unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (reader, mut writer) = stream.into_split();
+                let mut line = String::new();
+                BufReader::new(reader).read_line(&mut line).await.unwrap();
+                let request: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+                let response = match request["method"].as_str() {
+                    Some("public_key") => serde_json::json!({ "public_key": public.public_key() }),
+                    _ => {
+                        let message = request["message"].as_str().unwrap();
+                        let message = hex::decode(message.trim_start_matches("0x")).unwrap();
+                        serde_json::json!({ "signature": signing.sign(&message) })
+                    }
+                };
+                let mut response = serde_json::to_vec(&response).unwrap();
+                response.push(b'\n');
+                writer.write_all(&response).await.unwrap();
+            }
+        });
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_remote_signer_unix() {
+        let dir = std::env::temp_dir().join(format!("ekiden-signer-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let key_pair = KeyPair::generate();
+
+        let path = dir.join("good.sock");
+        serve(path.clone(), key_pair.clone(), key_pair.clone());
+        let signer = RemoteSigner::unix(path, None).await.unwrap();
+        assert_eq!(signer.public_key_hex(), key_pair.public_key());
+        let signature = signer.sign_intent(&intent()).await.unwrap();
+        assert_eq!(
+            signature,
+            Signer::sign_intent(&key_pair, &intent()).await.unwrap()
+        );
+
+        // Signatures from another key are rejected
+        let path = dir.join("bad.sock");
+        serve(path.clone(), key_pair, KeyPair::generate());
+        let signer = RemoteSigner::unix(path, None).await.unwrap();
+        assert!(signer.sign_intent(&intent()).await.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,257 @@
+use crate::aptos::vault::{TransactionReceipt, VaultContract};
+use crate::client::EkidenClient;
+use crate::error::{EkidenError, Result};
+use crate::types::{ListDepositsParams, ListWithdrawsParams, Pagination};
+use futures_util::{pin_mut, Stream, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+/// Kind of vault transfer followed by a [`TransferTracker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferKind {
+    Deposit,
+    Withdrawal,
+}
+
+/// Lifecycle stage of a tracked transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransferStatus {
+    /// The transaction was submitted to the node
+    Submitted { hash: String },
+    /// The transaction committed on chain
+    Confirmed {
+        hash: String,
+        receipt: TransactionReceipt,
+    },
+    /// The gateway indexed the transfer, with its gateway status
+    Indexed {
+        hash: String,
+        version: u64,
+        status: String,
+    },
+}
+
+impl TransferStatus {
+    /// Hash of the tracked transaction
+    pub fn hash(&self) -> &str {
+        match self {
+            TransferStatus::Submitted { hash }
+            | TransferStatus::Confirmed { hash, .. }
+            | TransferStatus::Indexed { hash, .. } => hash,
+        }
+    }
+
+    /// Whether the transfer has reached its final state
+    pub fn is_final(&self) -> bool {
+        match self {
+            TransferStatus::Indexed { status, .. } => !is_pending_status(status),
+            _ => false,
+        }
+    }
+}
+
+/// Timeouts and polling interval of a [`TransferTracker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackerConfig {
+    /// How long to wait for the transaction to commit on chain
+    pub chain_timeout: Duration,
+    /// How long to wait for the gateway to index a committed transfer
+    pub indexer_timeout: Duration,
+    /// Delay between gateway polls
+    pub poll_interval: Duration,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        Self {
+            chain_timeout: Duration::from_secs(60),
+            indexer_timeout: Duration::from_secs(120),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Follows a deposit or withdrawal from submission, to confirmation on chain,
+/// to its record in the gateway's `deposits` or `withdraws`.
+#[derive(Clone)]
+pub struct TransferTracker {
+    client: EkidenClient,
+    vault: Arc<VaultContract>,
+    config: TrackerConfig,
+}
+
+impl TransferTracker {
+    /// Create a tracker using `client` for gateway records and `vault` for
+    /// chain state
+    pub fn new(client: EkidenClient, vault: Arc<VaultContract>) -> Self {
+        Self {
+            client,
+            vault,
+            config: TrackerConfig::default(),
+        }
+    }
+
+    /// Set the timeouts and polling interval
+    pub fn with_config(mut self, config: TrackerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Stream the status transitions of a submitted transfer.
+    ///
+    /// The stream ends after the final gateway status, or with an
+    /// [`EkidenError::IndexerLag`] if the gateway does not index the
+    /// committed transaction in time.
+    pub fn track(
+        &self,
+        kind: TransferKind,
+        txn_hash: impl Into<String>,
+    ) -> impl Stream<Item = Result<TransferStatus>> + '_ {
+        let hash = txn_hash.into();
+        async_stream::try_stream! {
+            yield TransferStatus::Submitted { hash: hash.clone() };
+
+            let receipt = self
+                .vault
+                .wait_for_receipt(&hash, Some(self.config.chain_timeout))
+                .await
+                .map_err(EkidenError::from)?;
+            let version = receipt.version;
+            yield TransferStatus::Confirmed { hash: hash.clone(), receipt };
+
+            let started = Instant::now();
+            let mut last_status: Option<String> = None;
+            loop {
+                match self.find_record(kind, &hash, version).await {
+                    Ok(Some(status)) if last_status.as_ref() != Some(&status) => {
+                        last_status = Some(status.clone());
+                        let pending = is_pending_status(&status);
+                        yield TransferStatus::Indexed { hash: hash.clone(), version, status };
+                        if !pending {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) if e.is_retryable() => {
+                        warn!("Failed to poll gateway for {}: {}", hash, e);
+                    }
+                    Err(e) => Err(e)?,
+                }
+
+                let waited = started.elapsed();
+                if waited >= self.config.indexer_timeout {
+                    Err(EkidenError::IndexerLag {
+                        hash: hash.clone(),
+                        version,
+                        waited,
+                        last_status: last_status.clone(),
+                    })?;
+                }
+                debug!("Waiting for the gateway to index {} ({:?})", hash, kind);
+                tokio::time::sleep(self.config.poll_interval).await;
+            }
+        }
+    }
+
+    /// Follow a transfer to its final state and return it
+    pub async fn wait(
+        &self,
+        kind: TransferKind,
+        txn_hash: impl Into<String>,
+    ) -> Result<TransferStatus> {
+        let stream = self.track(kind, txn_hash);
+        pin_mut!(stream);
+        let mut last = None;
+        while let Some(status) = stream.next().await {
+            last = Some(status?);
+        }
+        last.ok_or_else(|| EkidenError::general("Transfer stream ended without a status"))
+    }
+
+    /// Find the gateway status of the transfer committed at `version`
+    async fn find_record(
+        &self,
+        kind: TransferKind,
+        hash: &str,
+        version: u64,
+    ) -> Result<Option<String>> {
+        let records = match kind {
+            TransferKind::Deposit => self
+                .client
+                .get_deposits(ListDepositsParams {
+                    user_addr: None,
+                    vault_addr: None,
+                    asset_addr: None,
+                    start_version: Some(version),
+                    end_version: Some(version),
+                    pagination: Pagination::default(),
+                })
+                .await?
+                .into_iter()
+                .map(|r| (r.tx_hash, r.status))
+                .collect::<Vec<_>>(),
+            TransferKind::Withdrawal => self
+                .client
+                .get_withdrawals(ListWithdrawsParams {
+                    user_addr: None,
+                    vault_addr: None,
+                    asset_addr: None,
+                    start_version: Some(version),
+                    end_version: Some(version),
+                    pagination: Pagination::default(),
+                })
+                .await?
+                .into_iter()
+                .map(|r| (r.tx_hash, r.status))
+                .collect(),
+        };
+        Ok(records
+            .into_iter()
+            .find(|(tx_hash, _)| same_hash(tx_hash, hash))
+            .map(|(_, status)| status))
+    }
+}
+
+/// Gateway statuses after which the record may still change
+fn is_pending_status(status: &str) -> bool {
+    matches!(
+        status.to_ascii_lowercase().as_str(),
+        "pending" | "processing"
+    )
+}
+
+/// Compare transaction hashes ignoring case and the `0x` prefix
+fn same_hash(a: &str, b: &str) -> bool {
+    let strip = |h: &str| h.trim_start_matches("0x").to_ascii_lowercase();
+    strip(a) == strip(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_hash() {
+        assert!(same_hash("0xABcd", "abcd"));
+        assert!(same_hash("0xabcd", "0xABCD"));
+        assert!(!same_hash("0xabcd", "0xabce"));
+    }
+
+    #[test]
+    fn test_final_status() {
+        let indexed = |status: &str| TransferStatus::Indexed {
+            hash: "0x1".to_string(),
+            version: 1,
+            status: status.to_string(),
+        };
+        assert!(!indexed("pending").is_final());
+        assert!(!indexed("Processing").is_final());
+        assert!(indexed("completed").is_final());
+        assert!(!TransferStatus::Submitted {
+            hash: "0x1".to_string()
+        }
+        .is_final());
+    }
+}
@@ -0,0 +1,385 @@
+use crate::client::EkidenClient;
+use crate::error::{EkidenError, Result};
+use crate::orders::{generate_client_order_id, IntentSubmission};
+use crate::types::{ActionPayload, OrderCreate, OrderCreateAction, OrderSide, OrderType, WsEvent};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// Direction in which the mark price must cross the trigger price
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerDirection {
+    /// Fires when the mark price rises to or above the trigger price
+    Above,
+    /// Fires when the mark price falls to or below the trigger price
+    Below,
+}
+
+impl TriggerDirection {
+    /// Get the trigger direction of a conditional order.
+    ///
+    /// A buy stop fires as the price rises and a sell stop as it falls;
+    /// take-profit orders fire the other way round.
+    pub fn for_order(order_type: OrderType, side: OrderSide) -> Option<Self> {
+        match (order_type, side) {
+            (OrderType::StopMarket | OrderType::StopLimit, OrderSide::Buy)
+            | (OrderType::TakeProfitMarket | OrderType::TakeProfitLimit, OrderSide::Sell) => {
+                Some(TriggerDirection::Above)
+            }
+            (OrderType::StopMarket | OrderType::StopLimit, OrderSide::Sell)
+            | (OrderType::TakeProfitMarket | OrderType::TakeProfitLimit, OrderSide::Buy) => {
+                Some(TriggerDirection::Below)
+            }
+            _ => None,
+        }
+    }
+
+    /// Check if the mark price has crossed the trigger price
+    pub fn is_triggered(&self, trigger_price: u64, mark_price: u64) -> bool {
+        match self {
+            TriggerDirection::Above => mark_price >= trigger_price,
+            TriggerDirection::Below => mark_price <= trigger_price,
+        }
+    }
+}
+
+/// A conditional order waiting for its trigger price
+#[derive(Debug, Clone)]
+pub struct PendingTrigger {
+    pub id: String,
+    pub order: OrderCreate,
+    pub trigger_price: u64,
+    pub direction: TriggerDirection,
+    /// Number of earlier firings whose outcome is unknown
+    pub attempts: u32,
+}
+
+impl PendingTrigger {
+    /// Create a pending trigger from a conditional order
+    pub fn new(order: OrderCreate) -> Result<Self> {
+        let order_type = OrderType::from_str(&order.r#type).map_err(EkidenError::validation)?;
+        let side = OrderSide::from_str(&order.side).map_err(EkidenError::validation)?;
+        let direction = TriggerDirection::for_order(order_type, side).ok_or_else(|| {
+            EkidenError::validation(format!("{} is not a conditional order type", order_type))
+        })?;
+        let trigger_price = order
+            .trigger_price
+            .ok_or_else(|| EkidenError::validation("Conditional order has no trigger price"))?;
+
+        Ok(Self {
+            id: order
+                .client_order_id
+                .clone()
+                .unwrap_or_else(generate_client_order_id),
+            order,
+            trigger_price,
+            direction,
+            attempts: 0,
+        })
+    }
+
+    /// The order placed once the trigger fires.
+    ///
+    /// It carries the trigger id as its client order id, so it can be found
+    /// again when the outcome of its submission is unknown.
+    pub fn child_order(&self) -> OrderCreate {
+        let order_type = OrderType::from_str(&self.order.r#type)
+            .map(|t| t.triggered_type())
+            .unwrap_or(OrderType::Market);
+
+        OrderCreate {
+            r#type: order_type.to_string(),
+            trigger_price: None,
+            client_order_id: Some(self.id.clone()),
+            ..self.order.clone()
+        }
+    }
+}
+
+/// Notification that a trigger fired and its child order was submitted
+#[derive(Debug, Clone)]
+pub struct TriggerFired {
+    pub trigger_id: String,
+    pub mark_price: u64,
+    pub order: OrderCreate,
+    /// The submission, or the error message if it failed
+    pub result: std::result::Result<IntentSubmission, String>,
+}
+
+/// Client-side engine for stop and take-profit orders.
+///
+/// For gateways without native conditional orders, the engine watches the
+/// mark price over the WebSocket and submits the child order through
+/// [`EkidenClient::send_intent_safe`] once its trigger price is crossed.
+pub struct TriggerEngine {
+    client: EkidenClient,
+    pending: Arc<Mutex<HashMap<String, PendingTrigger>>>,
+    fired: broadcast::Sender<TriggerFired>,
+}
+
+impl TriggerEngine {
+    /// Create a new trigger engine
+    pub fn new(client: EkidenClient) -> Self {
+        let (fired, _) = broadcast::channel(1000);
+        Self {
+            client,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            fired,
+        }
+    }
+
+    /// Add a conditional order, returning its trigger id
+    pub fn add(&self, order: OrderCreate) -> Result<String> {
+        let trigger = PendingTrigger::new(order)?;
+        let id = trigger.id.clone();
+        self.pending.lock().unwrap().insert(id.clone(), trigger);
+        Ok(id)
+    }
+
+    /// Remove a pending trigger
+    pub fn cancel(&self, trigger_id: &str) -> Option<PendingTrigger> {
+        self.pending.lock().unwrap().remove(trigger_id)
+    }
+
+    /// Get all pending triggers
+    pub fn pending(&self) -> Vec<PendingTrigger> {
+        self.pending.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Subscribe to fired triggers
+    pub fn subscribe_fired(&self) -> broadcast::Receiver<TriggerFired> {
+        self.fired.subscribe()
+    }
+
+    /// Remove and return the triggers of a market crossed by a mark price
+    pub fn take_triggered(&self, market_addr: &str, mark_price: u64) -> Vec<PendingTrigger> {
+        take_triggered(&self.pending, market_addr, mark_price)
+    }
+
+    /// Watch the mark price of a market and fire its triggers.
+    ///
+    /// The WebSocket must already be connected.
+    pub async fn run(&self, market_addr: &str) -> Result<JoinHandle<()>> {
+        let mut events = self.client.subscribe_mark_price(market_addr).await?;
+        let client = self.client.clone();
+        let pending = self.pending.clone();
+        let fired = self.fired.clone();
+        let market_addr = market_addr.to_string();
+
+        info!("Trigger engine watching mark price of {}", market_addr);
+        Ok(tokio::spawn(async move {
+            loop {
+                let mark_price = match events.recv().await {
+                    Ok(WsEvent::MarkPrice {
+                        market_addr: event_market,
+                        mark_price,
+                        ..
+                    }) if event_market == market_addr => mark_price,
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Trigger engine lagged by {} mark price updates", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                for trigger in take_triggered(&pending, &market_addr, mark_price) {
+                    Self::fire(&client, &pending, &fired, trigger, mark_price).await;
+                }
+            }
+            debug!("Trigger engine for {} stopped", market_addr);
+        }))
+    }
+
+    /// Submit the child order of a fired trigger.
+    ///
+    /// The order is sent with [`EkidenClient::send_intent_safe`], keyed by the
+    /// trigger id. If the outcome is still unknown afterwards, the trigger goes
+    /// back to `pending`; when it fires again, the order is looked up by id
+    /// first, so it is never placed twice.
+    async fn fire(
+        client: &EkidenClient,
+        pending: &Mutex<HashMap<String, PendingTrigger>>,
+        fired: &broadcast::Sender<TriggerFired>,
+        trigger: PendingTrigger,
+        mark_price: u64,
+    ) {
+        let order = trigger.child_order();
+        info!(
+            "Trigger {} fired at mark price {} (trigger {})",
+            trigger.id, mark_price, trigger.trigger_price
+        );
+
+        let result = match Self::submit_child(client, &trigger, &order).await {
+            Ok(submission) => Ok(submission),
+            Err(e) => {
+                error!("Failed to submit order for trigger {}: {}", trigger.id, e);
+                if e.is_retryable() {
+                    warn!("Outcome of trigger {} unknown, re-queueing", trigger.id);
+                    let requeued = PendingTrigger {
+                        attempts: trigger.attempts + 1,
+                        ..trigger.clone()
+                    };
+                    pending
+                        .lock()
+                        .unwrap()
+                        .insert(requeued.id.clone(), requeued);
+                }
+                Err(e.to_string())
+            }
+        };
+
+        let _ = fired.send(TriggerFired {
+            trigger_id: trigger.id,
+            mark_price,
+            order,
+            result,
+        });
+    }
+
+    async fn submit_child(
+        client: &EkidenClient,
+        trigger: &PendingTrigger,
+        order: &OrderCreate,
+    ) -> Result<IntentSubmission> {
+        // An earlier firing may have been committed after all
+        if trigger.attempts > 0 {
+            if let Some(existing) = client
+                .find_order_by_client_id(&order.market_addr, &trigger.id)
+                .await?
+            {
+                return Ok(IntentSubmission::AlreadyCommitted(vec![existing]));
+            }
+        }
+
+        let params = client
+            .signed_intent(ActionPayload::OrderCreate(OrderCreateAction {
+                orders: vec![order.clone()],
+            }))
+            .await?;
+        client.send_intent_safe(params).await
+    }
+}
+
+fn take_triggered(
+    pending: &Mutex<HashMap<String, PendingTrigger>>,
+    market_addr: &str,
+    mark_price: u64,
+) -> Vec<PendingTrigger> {
+    let mut pending = pending.lock().unwrap();
+    let ids: Vec<String> = pending
+        .values()
+        .filter(|t| {
+            t.order.market_addr == market_addr
+                && t.direction.is_triggered(t.trigger_price, mark_price)
+        })
+        .map(|t| t.id.clone())
+        .collect();
+
+    ids.iter().filter_map(|id| pending.remove(id)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orders::OrderBuilder;
+    use crate::utils::KeyPair;
+
+    #[test]
+    fn test_trigger_direction() {
+        assert_eq!(
+            TriggerDirection::for_order(OrderType::StopMarket, OrderSide::Sell),
+            Some(TriggerDirection::Below)
+        );
+        assert_eq!(
+            TriggerDirection::for_order(OrderType::TakeProfitLimit, OrderSide::Sell),
+            Some(TriggerDirection::Above)
+        );
+        assert_eq!(
+            TriggerDirection::for_order(OrderType::Limit, OrderSide::Buy),
+            None
+        );
+
+        assert!(TriggerDirection::Above.is_triggered(100, 100));
+        assert!(!TriggerDirection::Above.is_triggered(100, 99));
+        assert!(TriggerDirection::Below.is_triggered(100, 99));
+        assert!(!TriggerDirection::Below.is_triggered(100, 101));
+    }
+
+    #[tokio::test]
+    async fn test_take_triggered() {
+        let engine = TriggerEngine::new(EkidenClient::default_config().unwrap());
+        let stop = OrderBuilder::stop_limit("0x1", OrderSide::Sell, 1, 89, 90)
+            .reduce_only(true)
+            .build()
+            .unwrap();
+        let take_profit = OrderBuilder::take_profit_market("0x1", OrderSide::Sell, 1, 110)
+            .build()
+            .unwrap();
+        let stop_id = engine.add(stop).unwrap();
+        engine.add(take_profit).unwrap();
+
+        assert!(engine.take_triggered("0x1", 100).is_empty());
+        assert!(engine.take_triggered("0x2", 80).is_empty());
+
+        let fired = engine.take_triggered("0x1", 90);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].id, stop_id);
+        assert_eq!(engine.pending().len(), 1);
+
+        let child = fired[0].child_order();
+        assert_eq!(child.r#type, "limit");
+        assert_eq!(child.price, 89);
+        assert_eq!(child.trigger_price, None);
+        assert!(child.reduce_only);
+        assert_eq!(child.client_order_id.as_deref(), Some(stop_id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_failed_submit_requeues_trigger() {
+        // Nothing listens on port 1, so the submission fails to connect
+        let client = crate::client::EkidenClientBuilder::new()
+            .config(
+                crate::config::EkidenConfig::new("http://127.0.0.1:1")
+                    .unwrap()
+                    .with_max_retries(0),
+            )
+            .trading_private_key(KeyPair::generate().private_key())
+            .build()
+            .await
+            .unwrap();
+        let engine = TriggerEngine::new(client);
+        let mut fired_rx = engine.subscribe_fired();
+        let stop = OrderBuilder::stop_market("0x1", OrderSide::Sell, 1, 90)
+            .build()
+            .unwrap();
+        let stop_id = engine.add(stop).unwrap();
+
+        let triggered = engine.take_triggered("0x1", 90);
+        assert_eq!(triggered.len(), 1);
+        for trigger in triggered {
+            TriggerEngine::fire(&engine.client, &engine.pending, &engine.fired, trigger, 90).await;
+        }
+        let fired = fired_rx.recv().await.unwrap();
+        assert_eq!(fired.trigger_id, stop_id);
+        assert!(fired.result.is_err());
+
+        // Still pending, and fires again on the next mark price, after
+        // checking whether the first attempt went through
+        let again = engine.take_triggered("0x1", 89);
+        assert_eq!(again.len(), 1);
+        assert_eq!(again[0].id, stop_id);
+        assert_eq!(again[0].attempts, 1);
+    }
+
+    #[test]
+    fn test_non_conditional_order_rejected() {
+        let order = OrderBuilder::limit("0x1", OrderSide::Buy, 1, 100)
+            .build()
+            .unwrap();
+        assert!(PendingTrigger::new(order).is_err());
+    }
+}
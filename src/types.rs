@@ -1,3 +1,4 @@
+use crate::secret::REDACTED;
 use aptos_crypto::ed25519::{Ed25519PrivateKey, Ed25519Signature};
 use aptos_crypto::{signing_message, CryptoMaterialError};
 use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
@@ -57,11 +58,19 @@ pub struct AuthorizeParams {
     pub nonce: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AuthorizeResponse {
     pub token: String,
 }
 
+impl std::fmt::Debug for AuthorizeResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthorizeResponse")
+            .field("token", &REDACTED)
+            .finish()
+    }
+}
+
 // ===== Market Types =====
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -111,28 +120,129 @@ pub struct OrderResponse {
     pub market_addr: String,
     pub seq: u64,
     pub timestamp: u64,
+    /// Client-supplied identifier, if one was set when the order was created
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+    /// Mark price that triggers a conditional order
+    #[serde(default)]
+    pub trigger_price: Option<u64>,
+    #[serde(default)]
+    pub reduce_only: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListOrdersParams {
     pub market_addr: String,
     pub side: Option<String>,
+    pub client_order_id: Option<String>,
     #[serde(flatten)]
     pub pagination: Pagination,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum OrderSide {
     Buy,
     Sell,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+impl OrderSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderSide::Buy => "buy",
+            OrderSide::Sell => "sell",
+        }
+    }
+}
+
+impl Display for OrderSide {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OrderSide {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "buy" => Ok(OrderSide::Buy),
+            "sell" => Ok(OrderSide::Sell),
+            _ => Err(format!("Invalid OrderSide: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
 pub enum OrderType {
     Market,
     Limit,
+    // Market order released once the mark price moves through the trigger against the position
+    StopMarket,
+    // Limit order released once the mark price moves through the trigger against the position
+    StopLimit,
+    // Market order released once the mark price moves through the trigger in favour of the position
+    TakeProfitMarket,
+    // Limit order released once the mark price moves through the trigger in favour of the position
+    TakeProfitLimit,
+}
+
+impl OrderType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderType::Market => "market",
+            OrderType::Limit => "limit",
+            OrderType::StopMarket => "stop_market",
+            OrderType::StopLimit => "stop_limit",
+            OrderType::TakeProfitMarket => "take_profit_market",
+            OrderType::TakeProfitLimit => "take_profit_limit",
+        }
+    }
+
+    /// Whether the order waits for a trigger price before it is placed
+    pub fn is_conditional(&self) -> bool {
+        !matches!(self, OrderType::Market | OrderType::Limit)
+    }
+
+    /// Whether the order rests at a limit price
+    pub fn has_limit_price(&self) -> bool {
+        matches!(
+            self,
+            OrderType::Limit | OrderType::StopLimit | OrderType::TakeProfitLimit
+        )
+    }
+
+    /// The order type placed once a conditional order is triggered
+    pub fn triggered_type(&self) -> OrderType {
+        if self.has_limit_price() {
+            OrderType::Limit
+        } else {
+            OrderType::Market
+        }
+    }
+}
+
+impl Display for OrderType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OrderType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "market" => Ok(OrderType::Market),
+            "limit" => Ok(OrderType::Limit),
+            "stop_market" => Ok(OrderType::StopMarket),
+            "stop_limit" => Ok(OrderType::StopLimit),
+            "take_profit_market" => Ok(OrderType::TakeProfitMarket),
+            "take_profit_limit" => Ok(OrderType::TakeProfitLimit),
+            _ => Err(format!("Invalid OrderType: {}", s)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -212,6 +322,9 @@ pub struct ListPositionsParams {
 pub struct LeverageResponse {
     pub market_addr: String,
     pub leverage: u64,
+    /// Margin mode of the market, if reported by the gateway
+    #[serde(default)]
+    pub is_cross: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -223,6 +336,9 @@ pub struct GetUserLeverageParams {
 pub struct SetUserLeverageParams {
     pub market_addr: String,
     pub leverage: u64,
+    /// Switch the market to cross (true) or isolated (false) margin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_cross: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -326,6 +442,23 @@ pub struct OrderCreate {
     /// Time in force strategy. Defaults to GTC if not provided by the client.
     #[serde_as(as = "Option<DisplayFromStr>")]
     pub time_in_force: Option<TimeInForce>,
+
+    // The fields below are not part of the baseline order layout. They are
+    // left out of the JSON and of the signed BCS body when unset, so orders
+    // that do not use them sign the same bytes as before and are accepted by
+    // gateways that do not know them.
+    /// Optional client-supplied identifier, used to find the order again
+    /// when the outcome of a submission is unknown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
+
+    /// Mark price at which a stop or take-profit order is triggered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<u64>,
+
+    /// If set, the order may only reduce an existing position
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reduce_only: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -349,12 +482,36 @@ pub struct OrderCancelAllAction {
     pub market_addr: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderAmend {
+    /// The sid of the resting order to amend
+    pub sid: String,
+    /// The new price of the order
+    pub price: u64,
+    /// The new size of the order
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderAmendAction {
+    pub amends: Vec<OrderAmend>,
+}
+
+/// Cancels and creates orders within a single signed intent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderReplaceAction {
+    pub cancels: Vec<OrderCancel>,
+    pub orders: Vec<OrderCreate>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ActionPayload {
     OrderCreate(OrderCreateAction),
     OrderCancel(OrderCancelAction),
     OrderCancelAll(OrderCancelAllAction),
+    OrderAmend(OrderAmendAction),
+    OrderReplace(OrderReplaceAction),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -373,6 +530,8 @@ pub struct IntentSignatureBody {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderCreateOutput {
     pub sid: String,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -395,12 +554,30 @@ pub struct OrderCancelAllIntentOutput {
     pub outputs: Vec<OrderCancelOutput>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderAmendOutput {
+    pub sid: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderAmendIntentOutput {
+    pub outputs: Vec<OrderAmendOutput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderReplaceIntentOutput {
+    pub cancels: Vec<OrderCancelOutput>,
+    pub orders: Vec<OrderCreateOutput>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum IntentOutput {
     OrderCreate(OrderCreateIntentOutput),
     OrderCancel(OrderCancelIntentOutput),
     OrderCancelAll(OrderCancelAllIntentOutput),
+    OrderAmend(OrderAmendIntentOutput),
+    OrderReplace(OrderReplaceIntentOutput),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -562,6 +739,12 @@ pub enum WsEvent {
     PositionUpdate { position: PositionResponse },
     #[serde(rename = "balance_update")]
     BalanceUpdate { vault: VaultResponse },
+    #[serde(rename = "mark_price")]
+    MarkPrice {
+        market_addr: String,
+        mark_price: u64,
+        timestamp: u64,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -572,7 +755,7 @@ pub struct OrderbookLevel {
 
 // ===== Request Configuration =====
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct RequestConfig {
     pub method: reqwest::Method,
     pub headers: HashMap<String, String>,
@@ -581,6 +764,30 @@ pub struct RequestConfig {
     pub auth_required: bool,
 }
 
+impl std::fmt::Debug for RequestConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let headers: HashMap<&str, &str> = self
+            .headers
+            .iter()
+            .map(|(key, value)| {
+                if key.eq_ignore_ascii_case("authorization") {
+                    (key.as_str(), REDACTED)
+                } else {
+                    (key.as_str(), value.as_str())
+                }
+            })
+            .collect();
+
+        f.debug_struct("RequestConfig")
+            .field("method", &self.method)
+            .field("headers", &headers)
+            .field("query", &self.query)
+            .field("body", &self.body)
+            .field("auth_required", &self.auth_required)
+            .finish()
+    }
+}
+
 impl Default for RequestConfig {
     fn default() -> Self {
         Self {
@@ -703,6 +910,10 @@ impl ToQueryParams for ListOrdersParams {
             params.insert("side".to_string(), side.clone());
         }
 
+        if let Some(client_order_id) = &self.client_order_id {
+            params.insert("client_order_id".to_string(), client_order_id.clone());
+        }
+
         params
     }
 }
@@ -1,4 +1,5 @@
 use crate::error::{EkidenError, Result};
+use crate::secret::{SecretString, REDACTED};
 use aptos_crypto::{
     ed25519,
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
@@ -6,6 +7,7 @@ use aptos_crypto::{
 };
 use hex;
 use sha3::{Digest, Keccak256};
+use std::fmt::{Debug, Formatter};
 
 /// Cryptographic utilities for Ekiden SDK
 pub struct Crypto;
@@ -92,12 +94,23 @@ impl Crypto {
     }
 }
 
-/// Key pair for signing operations
-#[derive(Debug, Clone)]
+/// Key pair for signing operations.
+///
+/// The private key is zeroized on drop and redacted in `Debug` output.
+#[derive(Clone)]
 pub struct KeyPair {
     private_key: Ed25519PrivateKey,
 }
 
+impl Debug for KeyPair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyPair")
+            .field("public_key", &self.public_key())
+            .field("private_key", &REDACTED)
+            .finish()
+    }
+}
+
 impl KeyPair {
     /// Create a new key pair from a private key hex string
     pub fn from_private_key(private_key_hex: &str) -> Result<Self> {
@@ -105,6 +118,13 @@ impl KeyPair {
         Ok(Self { private_key })
     }
 
+    /// Create a new key pair from private key bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let private_key = Ed25519PrivateKey::try_from(bytes)
+            .map_err(|e| EkidenError::crypto(format!("Invalid private key: {}", e)))?;
+        Ok(Self { private_key })
+    }
+
     /// Generate a random key pair
     pub fn generate() -> Self {
         let rng = &mut rand::thread_rng();
@@ -113,8 +133,8 @@ impl KeyPair {
     }
 
     /// Get the private key as hex string
-    pub fn private_key(&self) -> String {
-        format!("0x{}", hex::encode(self.private_key.to_bytes()))
+    pub fn private_key(&self) -> SecretString {
+        SecretString::new(format!("0x{}", hex::encode(self.private_key.to_bytes())))
     }
 
     /// Get the public key as hex string
@@ -225,6 +245,7 @@ mod tests {
         let key_pair = KeyPair::generate();
         let public_key = key_pair.public_key();
         let private_key = key_pair.private_key();
+        let private_key = private_key.expose_secret();
 
         // Test that keys are valid hex
         assert!(public_key.starts_with("0x"));
@@ -233,6 +254,14 @@ mod tests {
         assert_eq!(private_key.len(), 66); // 0x + 64 hex chars
     }
 
+    #[test]
+    fn test_key_pair_debug_redacted() {
+        let key_pair = KeyPair::generate();
+        let debug = format!("{:?}", key_pair);
+        assert!(!debug.contains(&key_pair.private_key().expose_secret()[2..]));
+        assert!(debug.contains(REDACTED));
+    }
+
     #[test]
     fn test_signing_and_verification() {
         let key_pair = KeyPair::generate();
@@ -159,6 +159,15 @@ impl WebSocketClient {
         self.subscribe(&channel).await
     }
 
+    /// Subscribe to mark price updates for a market
+    pub async fn subscribe_mark_price(
+        &self,
+        market_addr: &str,
+    ) -> Result<broadcast::Receiver<WsEvent>> {
+        let channel = format!("mark_price/{}", market_addr);
+        self.subscribe(&channel).await
+    }
+
     // /// Subscribe to user-specific updates (orders, positions, balances)
     // pub async fn subscribe_user(&self, user_addr: &str) -> Result<broadcast::Receiver<WsEvent>> {
     //     let channel = format!("user/{}", user_addr);
@@ -299,6 +308,11 @@ pub mod channels {
         format!("trades/{}", market_addr)
     }
 
+    /// Create a mark price channel for a market
+    pub fn mark_price(market_addr: &str) -> String {
+        format!("mark_price/{}", market_addr)
+    }
+
     /// Create a user channel for user-specific updates
     pub fn user(user_addr: &str) -> String {
         format!("user/{}", user_addr)
@@ -366,6 +380,7 @@ mod tests {
     fn test_channel_helpers() {
         assert_eq!(channels::orderbook("0x123"), "orderbook/0x123");
         assert_eq!(channels::trades("0x456"), "trades/0x456");
+        assert_eq!(channels::mark_price("0x456"), "mark_price/0x456");
         assert_eq!(channels::user("0x789"), "user/0x789");
         assert_eq!(channels::candles("0x123", "1m"), "candles/0x123/1m");
     }
@@ -0,0 +1,223 @@
+//! Tests against a local Aptos node, started with `aptos node run-localnet`.
+//!
+//! Run with `cargo test --test localnet -- --ignored`. Set `APTOS_NODE_URL`
+//! to target another node. Tests of the Ekiden contract also need
+//! `EKIDEN_CONTRACT_ADDR` and `EKIDEN_ASSET_ADDR` of a published contract.
+
+use ekiden_rust_sdk::aptos::entry::{self, EntryFunctionSpec};
+use ekiden_rust_sdk::aptos::vault::{parse_address, VaultContract, LOCALNET_URL};
+
+fn node_url() -> String {
+    std::env::var("APTOS_NODE_URL").unwrap_or_else(|_| LOCALNET_URL.to_string())
+}
+
+fn env(name: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| panic!("{} must be set", name))
+}
+
+fn ekiden_vault() -> VaultContract {
+    VaultContract::with_node_url(
+        &env("EKIDEN_CONTRACT_ADDR"),
+        &env("EKIDEN_ASSET_ADDR"),
+        &node_url(),
+        None,
+    )
+    .unwrap()
+}
+
+/// Get the ABI of a published Ekiden module
+async fn module_abi(module: &str) -> serde_json::Value {
+    let url = format!(
+        "{}/accounts/{}/module/{}",
+        node_url(),
+        env("EKIDEN_CONTRACT_ADDR"),
+        module
+    );
+    let module: serde_json::Value = reqwest::get(&url).await.unwrap().json().await.unwrap();
+    match module.get("abi") {
+        Some(abi) if abi.is_object() => abi.clone(),
+        _ => panic!("No ABI at {}", url),
+    }
+}
+
+/// Get the exposed function `name` of a published module
+async fn exposed_function(module: &str, name: &str) -> serde_json::Value {
+    let abi = module_abi(module).await;
+    abi["exposed_functions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["name"] == name)
+        .unwrap_or_else(|| panic!("{}::{} is not published", abi["name"], name))
+        .clone()
+}
+
+/// Get the fields of the struct `name` of a published module, as name and type
+async fn struct_fields(module: &str, name: &str) -> Vec<(String, String)> {
+    let abi = module_abi(module).await;
+    let found = abi["structs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|s| s["name"] == name)
+        .unwrap_or_else(|| panic!("{}::{} is not published", abi["name"], name));
+    found["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| {
+            (
+                f["name"].as_str().unwrap().to_string(),
+                f["type"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+fn fields(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+    expected
+        .iter()
+        .map(|(name, ty)| (name.to_string(), ty.to_string()))
+        .collect()
+}
+
+fn params(function: &serde_json::Value) -> Vec<String> {
+    function["params"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p.as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+#[ignore = "requires a running localnet"]
+async fn test_localnet_chain_id() {
+    let vault = VaultContract::with_node_url("0x1", "0xa", &node_url(), None).unwrap();
+    vault.get_chain_id().await.unwrap();
+
+    let named = VaultContract::new("0x1", "0xa", "localnet").unwrap();
+    named.get_chain_id().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires a running localnet with the Ekiden contract"]
+async fn test_localnet_entry_functions_match_abi() {
+    let specs: [&EntryFunctionSpec; 10] = [
+        &entry::vault::DEPOSIT_INTO_FUNDING_WITH_TRANSFER_TO_CROSS_TRADING,
+        &entry::vault::WITHDRAW_FROM_USER,
+        &entry::vault::DEPOSIT_INTO_FUNDING,
+        &entry::vault::TRANSFER_FUNDING_TO_TRADING,
+        &entry::vault::TRANSFER_TRADING_TO_FUNDING,
+        &entry::vault::TRANSFER_CROSS_TO_ISOLATED,
+        &entry::vault::WITHDRAW_TO,
+        &entry::user::CREATE_EKIDEN_USER,
+        &entry::user::ROTATE_FUNDING_KEY,
+        &entry::user::ROTATE_TRADING_KEY,
+    ];
+    for spec in specs {
+        let function = exposed_function(spec.module, spec.name).await;
+        assert_eq!(function["is_entry"], true, "{}", spec.name);
+
+        let mut expected = vec!["&signer".to_string()];
+        expected.extend(spec.args.iter().map(|(_, t)| t.to_string()));
+        assert_eq!(params(&function), expected, "{}", spec.name);
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a running localnet with the Ekiden contract"]
+async fn test_localnet_views() {
+    let function = exposed_function("vault", "get_balance").await;
+    assert_eq!(function["is_view"], true);
+    assert_eq!(params(&function), vec!["address", "address"]);
+    assert_eq!(function["return"], serde_json::json!(["u64"]));
+
+    let function = exposed_function("user", "get_linked_accounts").await;
+    assert_eq!(function["is_view"], true);
+    assert_eq!(params(&function), vec!["address"]);
+    assert_eq!(
+        function["return"],
+        serde_json::json!(["address", "address"])
+    );
+
+    let function = exposed_function("user", "is_ekiden_user").await;
+    assert_eq!(function["is_view"], true);
+    assert_eq!(params(&function), vec!["address"]);
+    assert_eq!(function["return"], serde_json::json!(["bool"]));
+
+    // A fresh account holds nothing, in or outside the vault
+    let vault = ekiden_vault();
+    let account = parse_address(&format!("0x{}", "e".repeat(64))).unwrap();
+    let asset = vault.asset_addr;
+    assert_eq!(vault.get_vault_balance(&account, &asset).await.unwrap(), 0);
+    assert!(!vault.is_registered(&account).await.unwrap());
+    assert_eq!(vault.find_linked_accounts(&account).await.unwrap(), None);
+    assert_eq!(
+        vault
+            .get_fungible_asset_balance(&account, &asset)
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires a running localnet with the Ekiden contract"]
+async fn test_localnet_events_match_abi() {
+    // Fields are decoded by name, so their names and types must match
+    let events: [(&str, &str, &[(&str, &str)]); 5] = [
+        (
+            "vault",
+            "DepositEvent",
+            &[("user", "address"), ("asset", "address"), ("amount", "u64")],
+        ),
+        (
+            "vault",
+            "WithdrawEvent",
+            &[
+                ("user", "address"),
+                ("recipient", "address"),
+                ("asset", "address"),
+                ("amount", "u64"),
+            ],
+        ),
+        (
+            "vault",
+            "TransferEvent",
+            &[
+                ("from", "address"),
+                ("to", "address"),
+                ("asset", "address"),
+                ("amount", "u64"),
+            ],
+        ),
+        (
+            "user",
+            "UserCreatedEvent",
+            &[
+                ("root", "address"),
+                ("funding", "address"),
+                ("trading", "address"),
+            ],
+        ),
+        (
+            "user",
+            "KeyRotatedEvent",
+            &[
+                ("root", "address"),
+                ("previous", "address"),
+                ("current", "address"),
+            ],
+        ),
+    ];
+    for (module, name, expected) in events {
+        assert_eq!(
+            struct_fields(module, name).await,
+            fields(expected),
+            "{}::{}",
+            module,
+            name
+        );
+    }
+}
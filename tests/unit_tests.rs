@@ -1,3 +1,6 @@
+// The baseline tests still cover deprecated aliases such as `Auth::has_key_pair`
+#![allow(deprecated)]
+
 use ekiden_rust_sdk::{
     utils::{format, Crypto},
     Auth, EkidenClient, EkidenConfig, EkidenError, KeyPair, OrderSide, Pagination,
@@ -28,6 +31,7 @@ async fn test_key_pair_generation() {
 
     let public_key = key_pair.public_key();
     let private_key = key_pair.private_key();
+    let private_key = private_key.expose_secret();
 
     assert!(public_key.starts_with("0x"));
     assert_eq!(public_key.len(), 66); // 0x + 64 hex chars
@@ -40,7 +44,7 @@ async fn test_key_pair_from_private_key() {
     let key_pair1 = KeyPair::generate();
     let private_key_hex = key_pair1.private_key();
 
-    let key_pair2 = KeyPair::from_private_key(&private_key_hex).unwrap();
+    let key_pair2 = KeyPair::from_private_key(private_key_hex.expose_secret()).unwrap();
 
     assert_eq!(key_pair1.public_key(), key_pair2.public_key());
     assert_eq!(key_pair1.private_key(), key_pair2.private_key());
@@ -116,7 +120,7 @@ async fn test_client_auth_flow() {
 
     // Set private key
     client
-        .set_private_key(&key_pair.private_key())
+        .set_private_key(key_pair.private_key().expose_secret())
         .await
         .unwrap();
 
@@ -189,3 +193,189 @@ fn test_ws_request_serialization() {
     assert!(serialized.contains("subscribe"));
     assert!(serialized.contains("orderbook/0x123"));
 }
+
+#[test]
+fn test_amend_intent_signature() {
+    use aptos_crypto::signing_message;
+    use ekiden_rust_sdk::{
+        ActionPayload, IntentSignatureBody, OrderAmend, OrderAmendAction, SigningIntent,
+    };
+
+    let key_pair = KeyPair::generate();
+    let body = |price: u64| IntentSignatureBody {
+        payload: ActionPayload::OrderAmend(OrderAmendAction {
+            amends: vec![OrderAmend {
+                sid: "1".to_string(),
+                price,
+                size: 10,
+            }],
+        }),
+        nonce: 1,
+    };
+
+    let signature = key_pair.get_private_key().sign_intent(body(100)).unwrap();
+    let signature = format::ensure_hex_prefix(&hex::encode(signature.to_bytes()));
+    let message = signing_message(&body(100)).unwrap();
+    assert!(Crypto::verify_signature(&message, &signature, &key_pair.public_key()).unwrap());
+
+    // The amended price is covered by the signature
+    let message = signing_message(&body(101)).unwrap();
+    assert!(!Crypto::verify_signature(&message, &signature, &key_pair.public_key()).unwrap());
+}
+
+#[test]
+fn test_replace_intent_signature() {
+    use aptos_crypto::signing_message;
+    use ekiden_rust_sdk::{
+        ActionPayload, IntentSignatureBody, OrderCancel, OrderCreate, OrderReplaceAction,
+        SigningIntent,
+    };
+
+    let key_pair = KeyPair::generate();
+    let body = |sid: &str, price: u64| IntentSignatureBody {
+        payload: ActionPayload::OrderReplace(OrderReplaceAction {
+            cancels: vec![OrderCancel {
+                sid: sid.to_string(),
+            }],
+            orders: vec![OrderCreate {
+                side: "buy".to_string(),
+                size: 10,
+                price,
+                leverage: 5,
+                r#type: "limit".to_string(),
+                market_addr: "0x1".to_string(),
+                is_cross: true,
+                time_in_force: None,
+                client_order_id: Some("replace-1".to_string()),
+                trigger_price: None,
+                reduce_only: false,
+            }],
+        }),
+        nonce: 1,
+    };
+
+    // The action tag leads the BCS body, followed by both legs
+    let bytes = bcs::to_bytes(&body("1", 100)).unwrap();
+    assert_eq!(&bytes[..14], b"\x0dorder_replace");
+    let message = signing_message(&body("1", 100)).unwrap();
+    assert!(message.ends_with(&bytes));
+
+    let signature = key_pair
+        .get_private_key()
+        .sign_intent(body("1", 100))
+        .unwrap();
+    let signature = format::ensure_hex_prefix(&hex::encode(signature.to_bytes()));
+    assert!(Crypto::verify_signature(&message, &signature, &key_pair.public_key()).unwrap());
+
+    // Both the cancelled sid and the new order are covered by the signature
+    let message = signing_message(&body("2", 100)).unwrap();
+    assert!(!Crypto::verify_signature(&message, &signature, &key_pair.public_key()).unwrap());
+    let message = signing_message(&body("1", 101)).unwrap();
+    assert!(!Crypto::verify_signature(&message, &signature, &key_pair.public_key()).unwrap());
+}
+
+#[test]
+fn test_plain_order_keeps_baseline_signature() {
+    use aptos_crypto::signing_message;
+    use ekiden_rust_sdk::{ActionPayload, IntentSignatureBody, OrderCreate, OrderCreateAction};
+    use serde::Serialize;
+
+    // Layout of an order create intent before client order ids, trigger
+    // prices and reduce-only orders were added
+    #[derive(Serialize)]
+    struct BaselineOrderCreate {
+        side: String,
+        size: u64,
+        price: u64,
+        leverage: u64,
+        r#type: String,
+        market_addr: String,
+        is_cross: bool,
+        time_in_force: Option<String>,
+    }
+    // `ActionPayload` is internally tagged, so the tag is the first field
+    #[derive(Serialize)]
+    struct BaselineOrderCreateAction {
+        r#type: String,
+        orders: Vec<BaselineOrderCreate>,
+    }
+    #[derive(Serialize)]
+    struct BaselineBody {
+        payload: BaselineOrderCreateAction,
+        nonce: u64,
+    }
+
+    let order = OrderCreate {
+        side: "buy".to_string(),
+        size: 10,
+        price: 100,
+        leverage: 5,
+        r#type: "limit".to_string(),
+        market_addr: "0x1".to_string(),
+        is_cross: true,
+        time_in_force: None,
+        client_order_id: None,
+        trigger_price: None,
+        reduce_only: false,
+    };
+    let body = IntentSignatureBody {
+        payload: ActionPayload::OrderCreate(OrderCreateAction {
+            orders: vec![order.clone()],
+        }),
+        nonce: 7,
+    };
+    let baseline = bcs::to_bytes(&BaselineBody {
+        payload: BaselineOrderCreateAction {
+            r#type: "order_create".to_string(),
+            orders: vec![BaselineOrderCreate {
+                side: "buy".to_string(),
+                size: 10,
+                price: 100,
+                leverage: 5,
+                r#type: "limit".to_string(),
+                market_addr: "0x1".to_string(),
+                is_cross: true,
+                time_in_force: None,
+            }],
+        },
+        nonce: 7,
+    })
+    .unwrap();
+
+    assert_eq!(bcs::to_bytes(&body).unwrap(), baseline);
+    // The signing message is the type prefix followed by the BCS body
+    let message = signing_message(&body).unwrap();
+    assert!(message.ends_with(&baseline));
+
+    let json = serde_json::to_value(&order).unwrap();
+    assert!(json.get("client_order_id").is_none());
+    assert!(json.get("trigger_price").is_none());
+    assert!(json.get("reduce_only").is_none());
+
+    // Orders that use the new fields sign them
+    let reduce_only = IntentSignatureBody {
+        payload: ActionPayload::OrderCreate(OrderCreateAction {
+            orders: vec![OrderCreate {
+                reduce_only: true,
+                ..order
+            }],
+        }),
+        nonce: 7,
+    };
+    assert_ne!(bcs::to_bytes(&reduce_only).unwrap(), baseline);
+}
+
+#[test]
+fn test_request_config_debug_redacted() {
+    use ekiden_rust_sdk::{AuthorizeResponse, RequestConfig};
+
+    let config = RequestConfig::get().with_auth("secret_token".to_string());
+    let debug = format!("{:?}", config);
+    assert!(!debug.contains("secret_token"));
+    assert!(debug.contains("Authorization"));
+
+    let response = AuthorizeResponse {
+        token: "secret_token".to_string(),
+    };
+    assert!(!format!("{:?}", response).contains("secret_token"));
+}